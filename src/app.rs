@@ -1,15 +1,18 @@
 use std::borrow::Cow;
 
 use ansi_term::Color;
+use rustyline::completion::{Completer, Pair};
+use rustyline::config::CompletionType;
 use rustyline::error::ReadlineError;
 use rustyline::highlight::Highlighter;
-use rustyline::Editor;
-use rustyline_derive::{Completer, Helper, Hinter, Validator};
+use rustyline::{Context, Editor};
+use rustyline_derive::{Helper, Hinter, Validator};
 
-use crate::cmd::{parse_command, to_executor, CommandInput, CommandOutput};
+use crate::cmd::{parse_command, to_executor, CommandInput, CommandOutput, READ_ONLY_COMMANDS};
+use crate::completion::PaperCompleter;
 use crate::config::Config;
 use crate::error::Fallacy;
-use crate::state::State;
+use crate::state::{State, Trash, UndoHistory};
 
 pub struct App {
     config: Config,
@@ -17,8 +20,23 @@ pub struct App {
     editor: Editor<PromptHighlighter>,
 }
 
-#[derive(Completer, Helper, Validator, Hinter)]
-struct PromptHighlighter;
+#[derive(Helper, Validator, Hinter)]
+struct PromptHighlighter {
+    completer: PaperCompleter,
+}
+
+impl Completer for PromptHighlighter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.completer.complete(line, pos))
+    }
+}
 
 impl Highlighter for PromptHighlighter {
     fn highlight_prompt<'b, 's: 'b, 'p: 'b>(
@@ -41,10 +59,7 @@ impl App {
         let mut config: Config = match home::home_dir() {
             Some(mut p) => {
                 p.push(".config/reason/config.toml");
-                match confy::load_path(p) {
-                    Ok(config) => config,
-                    Err(e) => return Err(e.into()),
-                }
+                confy::load_path(p)?
             }
             None => {
                 eprintln!("Failed to find your home directory. Using default configuration.");
@@ -55,17 +70,22 @@ impl App {
         // Check and fix the contents of the config.
         config.validate()?;
 
-        // Load metadata state.
-        let state = State::load(&config.storage.paper_metadata)?;
+        // Load metadata state and undo history.
+        let mut state = State::load(&config.storage)?;
+        state.undo = UndoHistory::load(&config.storage);
+        state.trash = Trash::load(&config.storage);
 
         // Setup readline.
         let builder = rustyline::config::Builder::default();
         let rlconfig = builder
             .max_history_size(config.storage.max_history_size)
             .auto_add_history(true)
+            .completion_type(CompletionType::List)
             .build();
         let mut editor = Editor::with_config(rlconfig);
-        editor.set_helper(Some(PromptHighlighter {}));
+        editor.set_helper(Some(PromptHighlighter {
+            completer: PaperCompleter::default(),
+        }));
 
         // Maybe create and load from command history file.
         let history_path = &config.storage.command_history;
@@ -109,6 +129,12 @@ impl App {
         // Run the main loop.
         let mut run = true;
         while run {
+            // Update completion candidates, since the last command may have
+            // modified the paperbase.
+            if let Some(helper) = self.editor.helper_mut() {
+                helper.completer.refresh(&self.state);
+            }
+
             let readline = self.editor.readline(">> ");
             match readline {
                 Ok(line) => match self.execute(&line) {
@@ -125,10 +151,16 @@ impl App {
                 }
             }
 
-            // Save paper metadata state after every command.
-            if let Err(e) = self.state.store(&self.config.storage.paper_metadata) {
+            // Save paper metadata state, undo history, and trash after every command.
+            if let Err(e) = self.state.store(&self.config.storage) {
                 eprintln!("Could not save paper metadata: {}", e);
             }
+            if let Err(e) = self.state.undo.store(&self.config.storage) {
+                eprintln!("Could not save undo history: {}", e);
+            }
+            if let Err(e) = self.state.trash.store(&self.config.storage) {
+                eprintln!("Could not save the trash: {}", e);
+            }
         }
 
         Ok(())
@@ -140,7 +172,15 @@ impl App {
     /// - Save readline history
     pub fn terminate(&mut self) {
         // Save state to state file.
-        if let Err(e) = self.state.store(&self.config.storage.paper_metadata) {
+        if let Err(e) = self.state.store(&self.config.storage) {
+            eprintln!("Error during teardown: {}", e);
+        }
+
+        // Save undo history and trash.
+        if let Err(e) = self.state.undo.store(&self.config.storage) {
+            eprintln!("Error during teardown: {}", e);
+        }
+        if let Err(e) = self.state.trash.store(&self.config.storage) {
             eprintln!("Error during teardown: {}", e);
         }
 
@@ -160,7 +200,6 @@ impl App {
                 "Error during teardown: {}",
                 Fallacy::RLHistoryStoreFailed(history_path.to_owned(), e)
             );
-            return;
         }
     }
 
@@ -170,9 +209,36 @@ impl App {
         // Parse the command.
         let commands = parse_command(command)?;
 
-        // Run the command.
-        self.run_command(commands)
-            .map(|output| output.into_string(&self.state, &self.config))
+        // Run the command, recording changes to the paperbase and the trash
+        // so that they can be undone. The command may have changed papers even
+        // if it failed. Emptying the trash cannot be undone, and commands that
+        // only read need no record.
+        let undoable = !commands
+            .iter()
+            .any(|c| match c.first().map(|c| c.as_str()) {
+                Some("undo") | Some("redo") => true,
+                Some("trash") => c.get(1).is_some_and(|c| c == "empty"),
+                _ => false,
+            });
+        let read_only = commands.iter().all(|c| {
+            c.first()
+                .is_none_or(|c| c == "#" || READ_ONLY_COMMANDS.contains(&c.as_str()))
+        });
+        let before = if undoable && !read_only {
+            Some((self.state.papers.clone(), self.state.trash.entries.clone()))
+        } else {
+            None
+        };
+        let result = self.run_command(commands);
+        if let Some((papers, trash)) = before {
+            self.state.undo.record(
+                command,
+                (&papers, &trash),
+                (&self.state.papers, &self.state.trash.entries),
+                &self.config.storage,
+            );
+        }
+        result.map(|output| output.into_string(&self.state, &self.config))
     }
 
     fn run_command(&mut self, mut commands: Vec<Vec<String>>) -> Result<CommandOutput, Fallacy> {
@@ -0,0 +1,367 @@
+use std::fmt;
+
+use roxmltree::{Document, Node};
+
+use crate::error::Fallacy;
+use crate::paper::Paper;
+
+/// Namespace of arXiv extensions to Atom, e.g. `arxiv:doi`.
+const ARXIV_NS: &str = "http://arxiv.org/schemas/atom";
+
+/// The DOI prefix of arXiv.
+const DOI_PREFIX: &str = "10.48550";
+
+/// The base URL of the arXiv export API.
+pub const API_URL: &str = "https://export.arxiv.org/api/query";
+
+/// An arXiv identifier, e.g. '2105.11367v2' or 'hep-th/9901001'.
+#[derive(Debug, PartialEq)]
+pub struct ArxivId {
+    /// The identifier without its version.
+    pub id: String,
+    pub version: Option<u32>,
+}
+
+impl ArxivId {
+    /// Parse an identifier in any scheme, either bare, prefixed with
+    /// 'arXiv:', or in an abs or pdf URL of arxiv.org.
+    pub fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let id = match url::Url::parse(source) {
+            // 'arXiv:1412.6980' is a URL with the scheme 'arxiv'.
+            Ok(url) if url.scheme() == "arxiv" => url.path().to_owned(),
+            Ok(url) => {
+                if !url.host_str()?.ends_with("arxiv.org") {
+                    return None;
+                }
+                let path = url.path();
+                let id = path
+                    .strip_prefix("/abs/")
+                    .or_else(|| path.strip_prefix("/pdf/"))?;
+                id.trim_end_matches(".pdf").to_owned()
+            }
+            Err(_) => source.to_owned(),
+        };
+
+        // Split the version off.
+        let (id, version) = match id.rfind('v') {
+            Some(pos) if pos > 0 && id[..pos].ends_with(|c: char| c.is_ascii_digit()) => {
+                (&id[..pos], Some(id[pos + 1..].parse().ok()?))
+            }
+            _ => (&id[..], None),
+        };
+
+        let digits = |s: &str, lens: &[usize]| {
+            lens.contains(&s.len()) && s.chars().all(|c| c.is_ascii_digit())
+        };
+        let valid = match id.split_once('/') {
+            // Old-style identifiers since 1991, e.g. 'hep-th/9901001' or
+            // 'math.GT/0309136'.
+            Some((archive, number)) => {
+                let (archive, class) = archive.split_once('.').unwrap_or((archive, "AA"));
+                !archive.is_empty()
+                    && archive.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+                    && class.len() == 2
+                    && class.chars().all(|c| c.is_ascii_uppercase())
+                    && digits(number, &[7])
+            }
+            // New-style identifiers since 2007, e.g. '1412.6980' or
+            // '2105.11367'.
+            None => match id.split_once('.') {
+                Some((month, number)) => digits(month, &[4]) && digits(number, &[4, 5]),
+                None => false,
+            },
+        };
+        if !valid {
+            return None;
+        }
+        Some(Self {
+            id: id.to_owned(),
+            version,
+        })
+    }
+
+    /// Parse a DOI that arXiv registered for a paper, e.g.
+    /// '10.48550/arXiv.2105.11367'. Such DOIs have no version.
+    pub fn from_doi(doi: &str) -> Option<Self> {
+        let (prefix, suffix) = doi.trim().split_once('/')?;
+        if prefix != DOI_PREFIX || !suffix.get(..6)?.eq_ignore_ascii_case("arxiv.") {
+            return None;
+        }
+        Self::parse(&suffix[6..]).filter(|id| id.version.is_none())
+    }
+
+    /// The year of submission, from the 'YYMM' the number starts with.
+    pub fn year(&self) -> String {
+        let (century, number) = match self.id.split_once('/') {
+            Some((_, number)) if number >= "91" => ("19", number),
+            Some((_, number)) => ("20", number),
+            None => ("20", &self.id[..]),
+        };
+        format!("{}{}", century, &number[..2])
+    }
+}
+
+impl fmt::Display for ArxivId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "{}v{}", self.id, version),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+/// Metadata of one paper in an arXiv Atom feed.
+#[derive(Debug, PartialEq, Default)]
+pub struct ArxivEntry {
+    /// The identifier with its version, e.g. '2105.11367v2'.
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub summary: String,
+    /// When the first version was submitted, e.g. '2021-05-24T17:00:00Z'.
+    pub published: String,
+    pub primary_category: Option<String>,
+    pub doi: Option<String>,
+    pub journal_ref: Option<String>,
+}
+
+impl ArxivEntry {
+    /// The year the first version was submitted.
+    pub fn year(&self) -> String {
+        match ArxivId::parse(&self.id) {
+            Some(id) if self.published.len() < 4 => id.year(),
+            _ => self.published.chars().take(4).collect(),
+        }
+    }
+
+    /// Turn into a paper with venue arXiv. Everything other than the title,
+    /// authors, and year is kept in custom fields.
+    pub fn into_paper(self) -> Paper {
+        let mut paper = Paper {
+            venue: "arXiv".to_owned(),
+            year: self.year(),
+            ..Default::default()
+        };
+        let (id, version) = match ArxivId::parse(&self.id) {
+            Some(id) => (id.id, id.version.map(|v| v.to_string())),
+            None => (self.id, None),
+        };
+        let fields = [
+            ("arxiv", Some(id)),
+            ("version", version),
+            ("abstract", Some(self.summary)),
+            ("category", self.primary_category),
+            ("doi", self.doi),
+            ("journal_ref", self.journal_ref),
+        ];
+        for (key, value) in fields.iter() {
+            if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+                paper.fields.insert((*key).to_owned(), value.clone());
+            }
+        }
+        paper.title = self.title;
+        paper.authors = self.authors;
+        paper
+    }
+}
+
+/// Parse the first entry of an Atom feed returned by the arXiv export API.
+pub fn parse_feed(xml: &str) -> Result<ArxivEntry, Fallacy> {
+    let invalid = |reason: String| Fallacy::CurlInvalidResponse("arXiv".to_owned(), reason);
+    let doc = Document::parse(xml).map_err(|e| invalid(e.to_string()))?;
+    let entry = match doc
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name("entry"))
+    {
+        Some(entry) => entry,
+        None => return Err(Fallacy::CurlPaperNotFound("arXiv".to_owned())),
+    };
+
+    // The API reports errors, e.g. malformed identifiers, as an entry
+    // whose ID points to its error documentation.
+    let id = text(entry, "id").ok_or_else(|| invalid("Entry has no ID.".to_owned()))?;
+    if !id.contains("/abs/") {
+        return Err(invalid(text(entry, "summary").unwrap_or(id)));
+    }
+    let id = id.split("/abs/").nth(1).unwrap_or_default().to_owned();
+
+    let title = text(entry, "title")
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| Fallacy::CurlCannotFindTitle("No 'title' in the entry.".to_owned()))?;
+    let authors: Vec<String> = entry
+        .children()
+        .filter(|n| n.has_tag_name("author"))
+        .filter_map(|n| text(n, "name"))
+        .collect();
+    if authors.is_empty() {
+        return Err(Fallacy::CurlCannotFindAuthor(
+            "No 'author' in the entry.".to_owned(),
+        ));
+    }
+
+    Ok(ArxivEntry {
+        id,
+        title,
+        authors,
+        summary: text(entry, "summary").unwrap_or_default(),
+        published: text(entry, "published").unwrap_or_default(),
+        primary_category: entry
+            .children()
+            .find(|n| n.has_tag_name((ARXIV_NS, "primary_category")))
+            .and_then(|n| n.attribute("term"))
+            .map(|t| t.to_owned()),
+        doi: text(entry, (ARXIV_NS, "doi")),
+        journal_ref: text(entry, (ARXIV_NS, "journal_ref")),
+    })
+}
+
+/// The text of the first child with the tag, with whitespace collapsed.
+fn text<'a, 'input: 'a, N>(node: Node<'a, 'input>, tag: N) -> Option<String>
+where
+    N: Into<roxmltree::ExpandedName<'a, 'a>>,
+{
+    let tag = tag.into();
+    node.children()
+        .find(|n| n.has_tag_name(tag))
+        .and_then(|n| n.text())
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title type="html">ArXiv Query: id_list=2105.11367</title>
+  <entry>
+    <id>http://arxiv.org/abs/2105.11367v2</id>
+    <updated>2022-06-29T05:10:46Z</updated>
+    <published>2021-05-24T17:00:00Z</published>
+    <title>FedScale: Benchmarking Model and System Performance of Federated
+  Learning at Scale</title>
+    <summary>  We present FedScale, a federated learning benchmarking suite.
+</summary>
+    <author>
+      <name>Fan Lai</name>
+    </author>
+    <author>
+      <name>Yinwei Dai</name>
+    </author>
+    <arxiv:journal_ref xmlns:arxiv="http://arxiv.org/schemas/atom">ICML 2022</arxiv:journal_ref>
+    <link href="http://arxiv.org/abs/2105.11367v2" rel="alternate" type="text/html"/>
+    <link title="pdf" href="http://arxiv.org/pdf/2105.11367v2" rel="related" type="application/pdf"/>
+    <arxiv:primary_category xmlns:arxiv="http://arxiv.org/schemas/atom" term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+    <category term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+  </entry>
+</feed>
+"#;
+
+    #[test]
+    fn entry() {
+        let entry = parse_feed(FEED).unwrap();
+        assert_eq!(
+            entry,
+            ArxivEntry {
+                id: "2105.11367v2".to_owned(),
+                title: "FedScale: Benchmarking Model and System Performance of Federated Learning at Scale".to_owned(),
+                authors: vec!["Fan Lai".to_owned(), "Yinwei Dai".to_owned()],
+                summary: "We present FedScale, a federated learning benchmarking suite.".to_owned(),
+                published: "2021-05-24T17:00:00Z".to_owned(),
+                primary_category: Some("cs.LG".to_owned()),
+                doi: None,
+                journal_ref: Some("ICML 2022".to_owned()),
+            }
+        );
+
+        let paper = entry.into_paper();
+        assert_eq!(paper.venue, "arXiv");
+        assert_eq!(paper.year, "2021");
+        assert_eq!(paper.fields["version"], "2");
+        assert_eq!(paper.fields["arxiv"], "2105.11367");
+        assert_eq!(paper.fields["journal_ref"], "ICML 2022");
+        assert!(!paper.fields.contains_key("doi"));
+    }
+
+    #[test]
+    fn ids() {
+        let id = |id: &str, version| {
+            Some(ArxivId {
+                id: id.to_owned(),
+                version,
+            })
+        };
+        for (source, expected) in [
+            ("1412.6980", id("1412.6980", None)),
+            ("2105.11367v2", id("2105.11367", Some(2))),
+            ("arXiv:2105.11367", id("2105.11367", None)),
+            ("cs/0112017", id("cs/0112017", None)),
+            ("hep-th/9901001v3", id("hep-th/9901001", Some(3))),
+            ("math.GT/0309136", id("math.GT/0309136", None)),
+            (
+                "https://arxiv.org/abs/2105.11367v2",
+                id("2105.11367", Some(2)),
+            ),
+            (
+                "https://arxiv.org/pdf/2105.11367.pdf",
+                id("2105.11367", None),
+            ),
+            (
+                "https://arxiv.org/pdf/2105.11367v1",
+                id("2105.11367", Some(1)),
+            ),
+            (
+                "https://arxiv.org/abs/hep-th/9901001",
+                id("hep-th/9901001", None),
+            ),
+            ("https://usenix.org/abs/2105.11367", None),
+            ("2105.113", None),
+            ("2105.11367v", None),
+            ("Hep/9901001", None),
+            ("cs/01120", None),
+            ("10.1145/3492321", None),
+        ] {
+            assert_eq!(ArxivId::parse(source), expected, "{}", source);
+        }
+
+        for (doi, expected) in [
+            ("10.48550/arXiv.2105.11367", id("2105.11367", None)),
+            ("10.48550/ARXIV.hep-th/9901001", id("hep-th/9901001", None)),
+            ("10.48550/arXiv.2105.11367v2", None),
+            ("10.1145/arXiv.2105.11367", None),
+            ("10.48550/2105.11367", None),
+        ] {
+            assert_eq!(ArxivId::from_doi(doi), expected, "{}", doi);
+        }
+
+        assert_eq!(ArxivId::parse("hep-th/9901001").unwrap().year(), "1999");
+        assert_eq!(ArxivId::parse("cs/0112017").unwrap().year(), "2001");
+        assert_eq!(ArxivId::parse("1412.6980").unwrap().year(), "2014");
+        assert_eq!(
+            ArxivId::parse("hep-th/9901001v3").unwrap().to_string(),
+            "hep-th/9901001v3"
+        );
+    }
+
+    #[test]
+    fn error() {
+        let feed = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <entry>
+    <id>http://arxiv.org/api/errors#incorrect_id_format_for_1234</id>
+    <title>Error</title>
+    <summary>incorrect id format for 1234</summary>
+  </entry>
+</feed>"#;
+        assert!(matches!(
+            parse_feed(feed),
+            Err(Fallacy::CurlInvalidResponse(_, reason)) if reason == "incorrect id format for 1234"
+        ));
+        let empty = r#"<feed xmlns="http://www.w3.org/2005/Atom"></feed>"#;
+        assert!(matches!(
+            parse_feed(empty),
+            Err(Fallacy::CurlPaperNotFound(_))
+        ));
+    }
+}
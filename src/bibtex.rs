@@ -0,0 +1,604 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::str::Chars;
+
+use regex::Regex;
+
+use crate::paper::Paper;
+
+/// Words skipped when picking the title word of a citation key.
+const STOPWORDS: [&str; 12] = [
+    "a", "an", "the", "on", "of", "for", "in", "to", "towards", "toward", "with", "and",
+];
+
+/// Venue substrings that indicate a journal rather than a conference.
+const JOURNAL_HINTS: [&str; 12] = [
+    "journal",
+    "transactions",
+    "letters",
+    "magazine",
+    "review",
+    "communications of",
+    "jmlr",
+    "tocs",
+    "tpds",
+    "pvldb",
+    "cacm",
+    "ton",
+];
+
+/// Generate citation keys for all papers, in order.
+///
+/// Keys are of the form `{first author last name}{year}{first title word}`,
+/// e.g. `chung2020shadowtutor`. When more than one paper maps to the same key,
+/// the paper with the smallest ID keeps it and the others get their ID as a
+/// suffix, e.g. `chung2020shadowtutor-12`. Natural keys never contain '-', so
+/// suffixed keys cannot clash with them, and keys do not depend on the order
+/// of papers.
+pub fn citation_keys(papers: &[Paper]) -> Vec<String> {
+    let bases: Vec<String> = papers.iter().map(base_key).collect();
+    let mut owners: HashMap<&str, u64> = HashMap::new();
+    for (paper, base) in papers.iter().zip(&bases) {
+        let owner = owners.entry(base).or_insert(paper.id);
+        *owner = (*owner).min(paper.id);
+    }
+    papers
+        .iter()
+        .zip(&bases)
+        .map(|(paper, base)| {
+            if owners[base.as_str()] == paper.id {
+                base.clone()
+            } else {
+                format!("{}-{}", base, paper.id)
+            }
+        })
+        .collect()
+}
+
+/// Citation key without collision suffixes.
+fn base_key(paper: &Paper) -> String {
+    let author = paper
+        .authors
+        .first()
+        .and_then(|a| a.split_whitespace().last())
+        .unwrap_or("anonymous");
+    let word = paper
+        .title
+        .split(|c: char| c.is_whitespace() || c == '-' || c == ':')
+        .map(normalize)
+        .find(|w| !w.is_empty() && !STOPWORDS.contains(&w.as_str()))
+        .unwrap_or_default();
+    format!("{}{}{}", normalize(author), normalize(&paper.year), word)
+}
+
+/// Lowercase and drop everything that is not an ASCII letter or digit.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Format a paper into a BibTeX entry with the given citation key.
+pub fn to_entry(paper: &Paper, key: &str) -> String {
+    // Pad with spaces so that hints only match whole words.
+    let venue = paper
+        .venue
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .fold(" ".to_owned(), |acc, word| acc + word + " ");
+    let (entry_type, venue_field) = if venue.contains(" arxiv ") {
+        ("misc", "howpublished")
+    } else if JOURNAL_HINTS
+        .iter()
+        .any(|hint| venue.contains(&format!(" {} ", hint)))
+    {
+        ("article", "journal")
+    } else {
+        ("inproceedings", "booktitle")
+    };
+
+    let mut fields = vec![
+        ("title", format!("{{{}}}", escape(&paper.title))),
+        ("author", escape(&paper.authors.join(" and "))),
+        (venue_field, escape(&paper.venue)),
+        ("year", escape(&paper.year)),
+    ];
+    if let Some(nickname) = &paper.nickname {
+        fields.push(("note", escape(nickname)));
+    }
+
+    let mut entry = format!("@{}{{{},\n", entry_type, key);
+    for (name, value) in fields {
+        entry.push_str(&format!("  {} = {{{}}},\n", name, value));
+    }
+    entry.push_str("}\n");
+    entry
+}
+
+/// Escape characters that have special meaning in LaTeX or BibTeX.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A single entry parsed from a BibTeX file.
+#[derive(Debug, PartialEq)]
+pub struct BibEntry {
+    /// Lowercased entry type, e.g. 'inproceedings'.
+    pub entry_type: String,
+    pub key: String,
+    /// Lowercased field name -> field value with braces and quotes removed.
+    pub fields: HashMap<String, String>,
+}
+
+/// Parse all entries in a BibTeX file.
+/// `@comment`, `@string`, and `@preamble` entries are skipped, and so is
+/// anything outside entries. Entries that are malformed are returned as
+/// errors along with the citation key, if known.
+pub fn parse(content: &str) -> Vec<Result<BibEntry, String>> {
+    let mut entries = Vec::new();
+    let mut chars = content.chars().peekable();
+    while skip_past(&mut chars, '@') {
+        let entry_type = take_while(&mut chars, |c| c.is_ascii_alphanumeric()).to_lowercase();
+        skip_whitespace(&mut chars);
+        let close = match chars.next() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => {
+                entries.push(Err(format!("Malformed entry '@{}'.", entry_type)));
+                continue;
+            }
+        };
+        if matches!(entry_type.as_str(), "comment" | "string" | "preamble") {
+            take_delimited(&mut chars, close);
+            continue;
+        }
+        entries.push(parse_entry(&mut chars, entry_type, close));
+    }
+    entries
+}
+
+/// Parse the body of an entry, right after the opening brace.
+fn parse_entry(
+    chars: &mut Peekable<Chars>,
+    entry_type: String,
+    close: char,
+) -> Result<BibEntry, String> {
+    skip_whitespace(chars);
+    let key = take_while(chars, |c| c != ',' && c != close && !c.is_whitespace());
+    let mut fields = HashMap::new();
+    loop {
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some(c) if c == close => break,
+            _ => return Err(format!("Entry '{}' is not terminated.", key)),
+        }
+        skip_whitespace(chars);
+        if chars.peek() == Some(&close) {
+            chars.next();
+            break;
+        }
+        let name = take_while(chars, |c| c.is_ascii_alphanumeric() || "-_:".contains(c));
+        skip_whitespace(chars);
+        if name.is_empty() || chars.next() != Some('=') {
+            return Err(format!("Entry '{}' has a malformed field.", key));
+        }
+        // A value is a concatenation of braced strings, quoted strings,
+        // and bare words (numbers or macros) with '#'.
+        let mut value = String::new();
+        loop {
+            skip_whitespace(chars);
+            match chars.peek() {
+                Some('{') => {
+                    chars.next();
+                    value.push_str(&take_delimited(chars, '}'));
+                }
+                Some('"') => {
+                    chars.next();
+                    value.push_str(&take_delimited(chars, '"'));
+                }
+                Some(_) => value.push_str(&take_while(chars, |c| {
+                    c != ',' && c != close && c != '#' && !c.is_whitespace()
+                })),
+                None => return Err(format!("Entry '{}' is not terminated.", key)),
+            }
+            skip_whitespace(chars);
+            if chars.peek() == Some(&'#') {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        fields.insert(name.to_lowercase(), clean(&value));
+    }
+    Ok(BibEntry {
+        entry_type,
+        key,
+        fields,
+    })
+}
+
+/// Advance past the next occurrence of `target`. Returns false if not found.
+fn skip_past(chars: &mut Peekable<Chars>, target: char) -> bool {
+    chars.any(|c| c == target)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn take_while(chars: &mut Peekable<Chars>, pred: impl Fn(char) -> bool) -> String {
+    let mut taken = String::new();
+    while let Some(&c) = chars.peek() {
+        if !pred(c) {
+            break;
+        }
+        taken.push(c);
+        chars.next();
+    }
+    taken
+}
+
+/// Take everything up to the unbalanced closing delimiter, and consume it.
+/// Nested braces are kept in the returned string.
+fn take_delimited(chars: &mut Peekable<Chars>, close: char) -> String {
+    let mut taken = String::new();
+    let mut depth = 0;
+    for c in chars.by_ref() {
+        if c == close && depth == 0 {
+            break;
+        }
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        taken.push(c);
+    }
+    taken
+}
+
+/// Remove braces and LaTeX escapes, and collapse whitespace.
+/// Common accent commands like `{\'e}` are converted to the accented letter.
+fn clean(value: &str) -> String {
+    let mut cleaned = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '\\' => match chars.next() {
+                Some(accent @ ('\'' | '`' | '^' | '"' | '~')) => {
+                    let braced = chars.peek() == Some(&'{');
+                    if braced {
+                        chars.next();
+                    }
+                    if let Some(letter) = chars.next() {
+                        cleaned.push(accented(accent, letter).unwrap_or(letter));
+                    }
+                    if braced && chars.peek() == Some(&'}') {
+                        chars.next();
+                    }
+                }
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    let mut command = letter.to_string();
+                    command.push_str(&take_while(&mut chars, |c| c.is_ascii_alphabetic()));
+                    match command.as_str() {
+                        "textbackslash" => cleaned.push('\\'),
+                        _ => cleaned.push_str(&command),
+                    }
+                }
+                Some(escaped) => cleaned.push(escaped),
+                None => {}
+            },
+            _ => cleaned.push(c),
+        }
+    }
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Compose a letter with a LaTeX accent command.
+fn accented(accent: char, letter: char) -> Option<char> {
+    let (plain, composed) = match accent {
+        '\'' => ("aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+        '`' => ("aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        '^' => ("aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        '"' => ("aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+        '~' => ("anoANO", "ãñõÃÑÕ"),
+        _ => return None,
+    };
+    plain
+        .chars()
+        .position(|c| c == letter)
+        .and_then(|i| composed.chars().nth(i))
+}
+
+impl BibEntry {
+    /// Convert the entry into a paper.
+    /// Returns the reason as an error if required fields are missing.
+    pub fn to_paper(&self) -> Result<Paper, String> {
+        let get = |field: &str| self.fields.get(field).filter(|v| !v.is_empty());
+
+        let mut missing = Vec::new();
+        let title = get("title");
+        if title.is_none() {
+            missing.push("title");
+        }
+        let authors = get("author");
+        if authors.is_none() {
+            missing.push("author");
+        }
+        let year = get("year").or_else(|| get("date"));
+        if year.is_none() {
+            missing.push("year");
+        }
+        let is_arxiv = ["archiveprefix", "eprinttype", "journal", "publisher"]
+            .iter()
+            .any(|f| get(f).is_some_and(|v| v.to_lowercase().contains("arxiv")));
+        let venue = if is_arxiv {
+            Some("arXiv".to_owned())
+        } else {
+            get("booktitle")
+                .or_else(|| get("journal"))
+                .or_else(|| get("howpublished"))
+                .or_else(|| get("school"))
+                .or_else(|| get("institution"))
+                .map(|v| short_venue(v))
+        };
+        if venue.is_none() {
+            missing.push("booktitle or journal");
+        }
+        if !missing.is_empty() {
+            return Err(format!("missing {}", missing.join(", ")));
+        }
+
+        Ok(Paper {
+            // All fields are checked above.
+            title: title.unwrap().clone(),
+            authors: parse_authors(authors.unwrap()),
+            venue: venue.unwrap(),
+            // Dates are 'YYYY-MM-DD'.
+            year: year.unwrap().chars().take(4).collect(),
+            filepath: get("file").and_then(|f| parse_file_field(f)),
+            ..Default::default()
+        })
+    }
+}
+
+/// Convert a BibTeX author list into a list of 'First Last' names.
+/// Both 'Last, First' and 'First Last' forms are accepted.
+fn parse_authors(authors: &str) -> Vec<String> {
+    // The separator is case-insensitive, e.g. 'AND'.
+    let separator = Regex::new(r"(?i)\s+and\s+").unwrap();
+    separator
+        .split(authors)
+        .map(|author| {
+            let mut parts = author.splitn(2, ',').map(|s| s.trim());
+            match (parts.next(), parts.next()) {
+                (Some(last), Some(first)) if !first.is_empty() => format!("{} {}", first, last),
+                (Some(name), _) => name.to_owned(),
+                _ => String::new(),
+            }
+        })
+        .filter(|author| !author.is_empty())
+        .collect()
+}
+
+/// Shorten a venue to the acronym in parentheses, if any.
+/// For instance, 'Proceedings of the 15th USENIX Symposium on Operating
+/// Systems Design and Implementation (OSDI 21)' becomes 'OSDI'.
+pub fn short_venue(venue: &str) -> String {
+    for group in venue.split('(').skip(1) {
+        let group = group.split(')').next().unwrap_or_default();
+        if let Some(word) = group.split_whitespace().next() {
+            let letters = word.trim_matches(|c: char| !c.is_ascii_alphabetic());
+            if letters.len() >= 2 && letters.chars().all(|c| c.is_ascii_uppercase()) {
+                return letters.to_owned();
+            }
+        }
+    }
+    venue.to_owned()
+}
+
+/// Extract the first PDF path from a `file` field.
+/// Accepts both plain paths and the JabRef/Zotero form
+/// 'description:path:type', separated by ';'. Windows paths
+/// like 'C:\papers\a.pdf' keep their drive letter.
+fn parse_file_field(file: &str) -> Option<PathBuf> {
+    file.split(';')
+        .map(|f| {
+            let f = f.trim();
+            // Drop the file type, e.g. ':PDF' or ':application/pdf'.
+            let f = match f.rsplit_once(':') {
+                Some((rest, file_type)) if !file_type.to_lowercase().ends_with(".pdf") => rest,
+                _ => f,
+            };
+            // Drop the description, unless it is a drive letter.
+            match f.split_once(':') {
+                Some((drive, rest))
+                    if drive.len() == 1
+                        && drive.chars().all(|c| c.is_ascii_alphabetic())
+                        && rest.starts_with(['\\', '/']) =>
+                {
+                    f
+                }
+                Some((description, rest)) if !description.contains(['\\', '/']) => rest,
+                _ => f,
+            }
+        })
+        .find(|f| f.to_lowercase().ends_with(".pdf"))
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::paper;
+
+    #[test]
+    fn key_format() {
+        let papers = vec![
+            paper(0, "ShadowTutor: Distributed Partial Distillation")
+                .by(&["Jae-Won Chung", "Jae-Yun Kim"])
+                .at("ICPP")
+                .in_year("2020"),
+            paper(0, "The Tail at Scale")
+                .by(&["Jeffrey Dean"])
+                .at("CACM")
+                .in_year("2013"),
+            paper(0, "Untitled").at("arXiv").in_year("2021"),
+        ];
+        assert_eq!(
+            citation_keys(&papers),
+            vec![
+                "chung2020shadowtutor",
+                "dean2013tail",
+                "anonymous2021untitled"
+            ]
+        );
+    }
+
+    #[test]
+    fn key_collision() {
+        let mut papers = vec![
+            paper(7, "Zeus 2")
+                .by(&["Jie You"])
+                .at("NSDI")
+                .in_year("2023"),
+            paper(3, "Zeus").by(&["Jie You"]).at("NSDI").in_year("2023"),
+            paper(12, "Zeus 3")
+                .by(&["Jie You"])
+                .at("NSDI")
+                .in_year("2023"),
+            paper(5, "Zeusb")
+                .by(&["Jie You"])
+                .at("NSDI")
+                .in_year("2023"),
+        ];
+        assert_eq!(
+            citation_keys(&papers),
+            vec![
+                "you2023zeus-7",
+                "you2023zeus",
+                "you2023zeus-12",
+                "you2023zeusb"
+            ]
+        );
+
+        // Removing a paper does not change the keys of the others.
+        papers.remove(0);
+        assert_eq!(
+            citation_keys(&papers),
+            vec!["you2023zeus", "you2023zeus-12", "you2023zeusb"]
+        );
+    }
+
+    #[test]
+    fn escape_special() {
+        assert_eq!(
+            escape(r"{GPU}s & 50% of C:\temp"),
+            r"\{GPU\}s \& 50\% of C:\textbackslash{}temp"
+        );
+    }
+
+    #[test]
+    fn entry_type() {
+        let conf = paper(0, "A").by(&["B C"]).at("OSDI").in_year("2020");
+        let journal = paper(0, "A")
+            .by(&["B C"])
+            .at("ACM Transactions on Computer Systems")
+            .in_year("2020");
+        let arxiv = paper(0, "A").by(&["B C"]).at("arXiv").in_year("2020");
+        assert!(to_entry(&conf, "k").starts_with("@inproceedings{k,"));
+        assert!(to_entry(&journal, "k").starts_with("@article{k,"));
+        assert!(to_entry(&arxiv, "k").starts_with("@misc{k,"));
+    }
+
+    #[test]
+    fn parse_entries() {
+        let content = r#"
+            @comment{ignored}
+            @InProceedings{chung2020shadowtutor,
+              Title = {{ShadowTutor}: Distributed   Partial Distillation},
+              author = "Chung, Jae-Won and Jae-Yun Kim",
+              booktitle = {Proceedings of the 49th International Conference on Parallel Processing (ICPP '20)},
+              year = 2020,
+              file = {:/home/user/papers/shadowtutor.pdf:PDF},
+              note = {Jos{\'e} \& co in C:\textbackslash{}tmp},
+            }
+            @article{broken, title = {Broken}
+        "#;
+        let entries = parse(content);
+        assert_eq!(entries.len(), 2);
+        let entry = entries[0].as_ref().unwrap();
+        assert_eq!(entry.entry_type, "inproceedings");
+        assert_eq!(entry.key, "chung2020shadowtutor");
+        assert_eq!(
+            entry.fields["title"],
+            "ShadowTutor: Distributed Partial Distillation"
+        );
+        assert_eq!(entry.fields["note"], r"José & co in C:\tmp");
+        assert!(entries[1].is_err());
+
+        let paper = entry.to_paper().unwrap();
+        assert_eq!(paper.authors, vec!["Jae-Won Chung", "Jae-Yun Kim"]);
+        assert_eq!(paper.venue, "ICPP");
+        assert_eq!(paper.year, "2020");
+        assert_eq!(
+            paper.filepath,
+            Some(PathBuf::from("/home/user/papers/shadowtutor.pdf"))
+        );
+    }
+
+    #[test]
+    fn file_fields() {
+        for (field, expected) in [
+            ("/home/user/a.pdf", Some("/home/user/a.pdf")),
+            (":/home/user/a.pdf:PDF", Some("/home/user/a.pdf")),
+            (r"C:\papers\a.pdf", Some(r"C:\papers\a.pdf")),
+            (r":C:\papers\a.pdf:PDF", Some(r"C:\papers\a.pdf")),
+            (
+                r"Full Text PDF:C:\Users\me\a.pdf:application/pdf",
+                Some(r"C:\Users\me\a.pdf"),
+            ),
+            ("Snapshot:a.html:text/html;Paper:b.pdf:PDF", Some("b.pdf")),
+            ("a.html", None),
+        ] {
+            assert_eq!(
+                parse_file_field(field),
+                expected.map(PathBuf::from),
+                "{}",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn author_separator() {
+        assert_eq!(
+            parse_authors("Dean, Jeffrey AND Luiz Andre Barroso and Sandy"),
+            vec!["Jeffrey Dean", "Luiz Andre Barroso", "Sandy"]
+        );
+    }
+
+    #[test]
+    fn missing_fields() {
+        let entries = parse("@misc{key, title = {Only a title}}");
+        let reason = entries[0].as_ref().unwrap().to_paper().unwrap_err();
+        assert_eq!(reason, "missing author, year, booktitle or journal");
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::bibtex::{citation_keys, parse, to_entry};
+use crate::cmd::prelude::*;
+use crate::paper::PaperList;
+use crate::utils::expand_tilde;
+
+pub static MAN: &str = include_str!("../../man/bib.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Import from a BibTeX file.
+    if input.args.get(1).map(|s| s.as_str()) == Some("-i") {
+        return match input.args.get(2) {
+            Some(path) => import(&expand_tilde(&PathBuf::from(path))?, state, config),
+            None => Err(Fallacy::BibNoInputPath),
+        };
+    }
+
+    // Parse the output file path.
+    let mut output = None;
+    if input.args.get(1).map(|s| s.as_str()) == Some("-o") {
+        match input.args.get(2) {
+            Some(path) => output = Some(expand_tilde(&PathBuf::from(path))?),
+            None => return Err(Fallacy::BibNoOutputPath),
+        }
+        input.args.drain(1..3);
+    }
+
+    // Build paper list from input.
+    let selected = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list.0,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::filter(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list.0,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!("ls did not return CommandOutput::Papers."),
+            }
+        }
+    };
+
+    // Citation keys are generated with the whole paperbase so that the key of
+    // a paper does not depend on which papers are exported together.
+    let keys = citation_keys(&state.papers);
+    let entries: Vec<_> = selected
+        .iter()
+        .filter_map(|&id| state.position(id))
+        .map(|ind| to_entry(&state.papers[ind], &keys[ind]))
+        .collect();
+    let bib = entries.join("\n");
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, bib)?;
+            Ok(CommandOutput::Message(format!(
+                "Wrote {} {} to {:?}.\n",
+                entries.len(),
+                if entries.len() != 1 {
+                    "entries"
+                } else {
+                    "entry"
+                },
+                path
+            )))
+        }
+        None => Ok(CommandOutput::Message(bib)),
+    }
+}
+
+/// Create papers from the entries of a BibTeX file.
+fn import(path: &Path, state: &mut State, config: &Config) -> Result<CommandOutput, Fallacy> {
+    if !path.is_file() {
+        return Err(Fallacy::PathDoesNotExist(path.to_owned()));
+    }
+    let content = std::fs::read_to_string(path)?;
+    // Relative `file` fields are relative to the BibTeX file.
+    let bib_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    // Titles are compared after removing case and punctuation.
+    let normalize = |title: &str| -> String {
+        title
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(|c| c.to_lowercase())
+            .collect()
+    };
+    let mut titles: HashSet<_> = state.papers.iter().map(|p| normalize(&p.title)).collect();
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    let mut warnings = Vec::new();
+    for entry in parse(&content) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(reason) => {
+                skipped.push(reason);
+                continue;
+            }
+        };
+        let mut paper = match entry.to_paper() {
+            Ok(paper) => paper,
+            Err(reason) => {
+                skipped.push(format!("'{}': {}.", entry.key, reason));
+                continue;
+            }
+        };
+        if !titles.insert(normalize(&paper.title)) {
+            skipped.push(format!("'{}': duplicate of an existing paper.", entry.key));
+            continue;
+        }
+
+        // Keep the file path only if the file exists, relative to
+        // `file_dir` if the file is inside it.
+        if let Some(filepath) = paper.filepath.take() {
+            let filepath = bib_dir.join(expand_tilde(&filepath)?);
+            if filepath.is_file() {
+                paper.filepath = Some(match filepath.strip_prefix(&config.storage.file_dir) {
+                    Ok(relative) => relative.to_owned(),
+                    Err(_) => filepath,
+                });
+            } else {
+                warnings.push(format!("'{}': file {:?} not found.", entry.key, filepath));
+            }
+        }
+
+        imported.push(state.add(paper));
+    }
+
+    // Report.
+    if !warnings.is_empty() {
+        eprintln!("Imported without files:");
+        for warning in warnings {
+            eprintln!("  {}", warning);
+        }
+    }
+    if !skipped.is_empty() {
+        eprintln!(
+            "Skipped {} {}:",
+            skipped.len(),
+            if skipped.len() != 1 {
+                "entries"
+            } else {
+                "entry"
+            }
+        );
+        for reason in skipped {
+            eprintln!("  {}", reason);
+        }
+    }
+
+    Ok(CommandOutput::Papers(PaperList(imported)))
+}
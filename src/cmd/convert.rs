@@ -0,0 +1,80 @@
+use crate::cmd::prelude::*;
+use crate::config::StorageBackend;
+use crate::utils::confirm;
+
+pub static MAN: &str = include_str!("../../man/convert.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let (backend, name) = match input.args.get(1).map(|s| s.as_str()) {
+        Some("yaml") => (StorageBackend::Yaml, "yaml"),
+        Some("sqlite") => (StorageBackend::Sqlite, "sqlite"),
+        Some(name) => return Err(Fallacy::ConvertUnknownBackend(name.to_owned())),
+        None => return Err(Fallacy::ConvertNoBackend),
+    };
+    if backend == config.storage.backend() {
+        return Err(Fallacy::ConvertSameBackend(name.to_owned()));
+    }
+
+    // Read what is already there, so that it is not overwritten by accident.
+    let (path, existing) = match backend {
+        StorageBackend::Yaml => {
+            let path = config.storage.paper_metadata.clone();
+            let existing = match path.exists() {
+                true => State::read(&path)?.len(),
+                false => 0,
+            };
+            (path, existing)
+        }
+        StorageBackend::Sqlite => {
+            let path = config.storage.paper_database();
+            let existing = match path.exists() {
+                true => State::read_database(&path)?.len(),
+                false => 0,
+            };
+            (path, existing)
+        }
+    };
+    if existing > 0 {
+        confirm(
+            format!(
+                "Replace {} {} in {:?} with {} {}?",
+                existing,
+                if existing != 1 { "papers" } else { "paper" },
+                path,
+                state.papers.len(),
+                if state.papers.len() != 1 {
+                    "papers"
+                } else {
+                    "paper"
+                },
+            ),
+            false,
+        )?;
+    }
+
+    match backend {
+        StorageBackend::Yaml => State::write(&path, &state.papers, state.next_id())?,
+        StorageBackend::Sqlite => State::write_database(&path, &state.papers, state.next_id())?,
+    }
+
+    Ok(CommandOutput::Message(format!(
+        "Wrote {} {} to {:?}. Set `storage.backend` to '{}' in the config to use {}.\n",
+        state.papers.len(),
+        if state.papers.len() != 1 {
+            "papers"
+        } else {
+            "paper"
+        },
+        path,
+        name,
+        if state.papers.len() != 1 {
+            "them"
+        } else {
+            "it"
+        },
+    )))
+}
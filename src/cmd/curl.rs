@@ -1,10 +1,14 @@
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{Cursor, Write};
 use std::time::Duration;
 
+use crate::arxiv;
 use crate::cmd::prelude::*;
+use crate::crossref;
+use crate::dblp;
+use crate::openreview::{self, Submission};
 use crate::paper::{Paper, PaperList};
-use crate::utils::{as_filename, ask_for, confirm, make_unique_path, select};
+use crate::utils::{as_filename, ask_for, confirm, http_client, make_unique_path, select};
 
 use soup::prelude::*;
 use tempfile::NamedTempFile;
@@ -16,15 +20,51 @@ pub fn execute(
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
-    // Check if source is given.
-    let mut args = input.args;
-    if args.len() <= 1 {
-        return Err(Fallacy::CurlNoSource);
+    // Parse options and check if source is given.
+    let mut keywords_as_labels = false;
+    let mut save_reviews = false;
+    let mut source = None;
+    for arg in input.args.into_iter().skip(1) {
+        match arg.as_str() {
+            "-l" => keywords_as_labels = true,
+            "-r" => save_reviews = true,
+            _ if source.is_none() => source = Some(arg),
+            _ => return Err(Fallacy::CurlExtraSource(arg)),
+        }
+    }
+    let url = source.ok_or(Fallacy::CurlNoSource)?;
+
+    // Options that only make sense for OpenReview.
+    let forum = openreview::parse_forum_id(&url);
+    if forum.is_none() {
+        if keywords_as_labels {
+            return Err(Fallacy::CurlOpenReviewOnly("-l".to_owned()));
+        }
+        if save_reviews {
+            return Err(Fallacy::CurlOpenReviewOnly("-r".to_owned()));
+        }
     }
 
     // Parse source and route to appropriate function.
-    let url = args.remove(1);
-    let paper = if url.contains("arxiv") {
+    let mut reviews = None;
+    let paper = if let Some(forum) = forum {
+        let submission = from_openreview(&forum, keywords_as_labels, config)?;
+        if save_reviews {
+            if submission.reviews.is_none() {
+                eprintln!("No reviews found.");
+            }
+            reviews = submission.reviews;
+        }
+        submission.paper
+    } else if let Some(doi) = crossref::parse_doi(&url) {
+        // arXiv registers its DOIs with DataCite, not Crossref.
+        match arxiv::ArxivId::from_doi(&doi) {
+            Some(id) => from_arxiv(&id.to_string(), config)?,
+            None => from_doi(&doi, config)?,
+        }
+    } else if let Some(key) = dblp::parse_key(&url) {
+        from_dblp(&key, config)?
+    } else if url.contains("arxiv") || arxiv::ArxivId::parse(&url).is_some() {
         from_arxiv(url.as_ref(), config)?
     } else if url.contains("usenix") {
         from_usenix(url.as_ref(), config)?
@@ -33,97 +73,44 @@ pub fn execute(
     };
 
     // Add paper to state.
-    state.papers.push(paper);
-
-    Ok(CommandOutput::Papers(PaperList(vec![
-        state.papers.len() - 1,
-    ])))
-}
-
-fn from_arxiv(url: &str, config: &Config) -> Result<Paper, Fallacy> {
-    // NOTE: There's the arXiv export API, but we need to parse XML to use that.
-    //       xml-rs seems good enough, but I'd rather not add another dependency
-    //       just for this. As of now our use case is simple and parsing HTML
-    //       with soup seems tractable. However, if things get more complicated,
-    //       consider switching to using the API.
+    let id = state.add(paper);
 
-    println!("Fetching from arXiv.");
-
-    // Parse and validate url.
-    // https://arxiv.org/abs/2208.06102
-    // https://arxiv.org/pdf/2208.06102.pdf
-    let parsed_url = url::Url::parse(url)?;
-    if parsed_url.cannot_be_a_base() {
-        return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
-    }
-    // Calling unwrap() doesn't panic if !parsed_url.cannot_be_a_base().
-    let mut segments: Vec<_> = parsed_url.path_segments().unwrap().collect();
-    if segments.len() != 2
-        || !parsed_url.has_host()
-        || !parsed_url.host_str().unwrap().ends_with("arxiv.org")
-        || (segments[0] != "abs" && segments[0] != "pdf")
-    {
-        confirm(
-            "URL of form https://arxiv.org/abs/{identifier} expected. Just continue?".to_string(),
-            true,
-        )?;
-    }
-    // Convert https://arvix.org/pdf urls to https://arxiv.org/abs urls.
-    if segments[0] == "pdf" {
-        segments[0] = "abs";
-        segments[1] = segments[1].trim_end_matches(".pdf");
-    }
-    let pieces: Vec<_> = segments[1].split('.').collect();
-    if pieces.len() != 2 || pieces[0].len() != 4 || pieces[1].len() != 5 {
-        return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
-    }
-    if !pieces.iter().all(|p| p.chars().all(|c| c.is_numeric())) {
-        return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
+    // Start the note of the paper with its reviews.
+    if let (Some(reviews), Some(paper)) = (reviews, state.paper_mut(id)) {
+        if let Some(note) = paper.notepath(config, true)? {
+            let mut file = OpenOptions::new().append(true).open(&note)?;
+            write!(file, "{}", reviews)?;
+        }
     }
-    let venue = "arXiv".to_owned();
-    let year = format!("20{}", &pieces[0][..2]);
 
-    // Initialize HTTP client.
-    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let client = reqwest::blocking::ClientBuilder::new()
-        .user_agent(user_agent)
-        .build()?;
+    Ok(CommandOutput::Papers(PaperList(vec![id])))
+}
 
-    // Parse title.
-    let res = client
-        .get(format!("https://arxiv.org/abs/{}", segments[1]))
-        .send()?;
-    let soup = Soup::from_reader(res)?;
-    let title = match soup.class("title").find() {
-        Some(title) => title,
-        None => {
-            return Err(Fallacy::CurlCannotFindTitle(
-                "No class named 'title' found.".to_owned(),
-            ))
-        }
-    };
-    let title = match title.children().last() {
-        Some(title) => title.text(),
-        None => {
-            return Err(Fallacy::CurlCannotFindTitle(
-                "Class 'title' has no children.".to_owned(),
-            ))
-        }
-    };
+fn from_arxiv(source: &str, config: &Config) -> Result<Paper, Fallacy> {
+    eprintln!("Fetching from arXiv.");
 
-    // Parse author list.
-    let authors = match soup.class("authors").find() {
-        Some(authors) => authors,
-        None => {
-            return Err(Fallacy::CurlCannotFindAuthor(
-                "No class named 'authors' found.".to_owned(),
-            ))
-        }
+    // Parse the identifier, e.g.
+    // 2208.06102, arXiv:2208.06102v2, hep-th/9901001,
+    // https://arxiv.org/abs/2208.06102, https://arxiv.org/pdf/2208.06102v1.pdf
+    let id = match arxiv::ArxivId::parse(source) {
+        Some(id) => id,
+        None => return Err(Fallacy::CurlInvalidSourceUrl(source.to_owned())),
     };
-    let authors: Vec<String> = authors.tag("a").find_all().map(|a| a.text()).collect();
 
-    // Download paper PDF.
-    let url = format!("https://arxiv.org/pdf/{}.pdf", segments[1]);
+    let client = http_client()?;
+
+    // Fetch metadata from the export API.
+    let feed = client
+        .get(arxiv::API_URL)
+        .query(&[("id_list", id.to_string())])
+        .send()?
+        .error_for_status()?
+        .text()?;
+    let entry = arxiv::parse_feed(&feed)?;
+
+    // Download paper PDF. The entry has the requested version, or the
+    // latest one if none was given.
+    let url = format!("https://arxiv.org/pdf/{}.pdf", entry.id);
     let mut cursor = Cursor::new(
         client
             .get(url)
@@ -131,23 +118,78 @@ fn from_arxiv(url: &str, config: &Config) -> Result<Paper, Fallacy> {
             .send()?
             .bytes()?,
     );
-    let filename = as_filename(&title);
+    let filename = as_filename(&entry.title);
     let filepath = make_unique_path(&config.storage.file_dir, &filename, ".pdf");
     let mut file = File::create(&filepath)?;
     std::io::copy(&mut cursor, &mut file)?;
 
     Ok(Paper {
-        title,
-        authors,
-        venue,
-        year,
         filepath: Some(filepath),
-        ..Default::default()
+        ..entry.into_paper()
     })
 }
 
+fn from_doi(doi: &str, config: &Config) -> Result<Paper, Fallacy> {
+    eprintln!("Fetching from Crossref.");
+
+    let client = http_client()?;
+
+    // Crossref does not link to freely available PDFs.
+    let paper = crossref::fetch(&client, config.sources.crossref_url(), doi)?;
+    eprintln!("Paper PDF not available. Skipping PDF download.");
+    Ok(paper)
+}
+
+fn from_dblp(key: &str, config: &Config) -> Result<Paper, Fallacy> {
+    eprintln!("Fetching from DBLP.");
+
+    let client = http_client()?;
+
+    let record = dblp::fetch(&client, config.sources.dblp_url(), key)?;
+    eprintln!("Paper PDF not available. Skipping PDF download.");
+    Ok(record.into_paper())
+}
+
+fn from_openreview(
+    forum: &str,
+    keywords_as_labels: bool,
+    config: &Config,
+) -> Result<Submission, Fallacy> {
+    eprintln!("Fetching from OpenReview.");
+
+    let client = http_client()?;
+
+    let mut submission = openreview::fetch(&client, config.sources.openreview_url(), forum)?;
+    if keywords_as_labels {
+        submission
+            .paper
+            .labels
+            .extend(submission.keywords.iter().cloned());
+    }
+
+    // Download paper PDF. Withdrawn submissions may not have one.
+    let res = client
+        .get(openreview::pdf_url(config.sources.openreview_url()))
+        .query(&[("id", forum)])
+        .timeout(Duration::from_secs(90))
+        .send()
+        .and_then(|res| res.error_for_status());
+    if let Ok(res) = res {
+        let mut cursor = Cursor::new(res.bytes()?);
+        let filename = as_filename(&submission.paper.title);
+        let filepath = make_unique_path(&config.storage.file_dir, &filename, ".pdf");
+        let mut file = File::create(&filepath)?;
+        std::io::copy(&mut cursor, &mut file)?;
+        submission.paper.filepath = Some(filepath);
+    } else {
+        eprintln!("Paper PDF not found. Skipping PDF download.");
+    }
+
+    Ok(submission)
+}
+
 fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
-    println!("Fetching from usenix.org.");
+    eprintln!("Fetching from usenix.org.");
 
     // Parse and validate source url.
     // https://usenix.org/conference/atc21/presentation/lee
@@ -178,11 +220,7 @@ fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
     };
     let year = format!("20{}", &conf[conf.len() - 2..]);
 
-    // Initialize HTTP client.
-    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let client = reqwest::blocking::ClientBuilder::new()
-        .user_agent(user_agent)
-        .build()?;
+    let client = http_client()?;
 
     // Parse title.
     let res = client.get(url).send()?;
@@ -263,7 +301,7 @@ fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
         std::io::copy(&mut cursor, &mut file)?;
         Some(filepath)
     } else {
-        println!("Paper PDF not found. Skipping PDF download.");
+        eprintln!("Paper PDF not found. Skipping PDF download.");
         None
     };
 
@@ -279,7 +317,7 @@ fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
 }
 
 fn from_pdf(url: &str, config: &Config) -> Result<Paper, Fallacy> {
-    println!("Treating as raw PDF.");
+    eprintln!("Treating as raw PDF.");
 
     // Parse and validate source url.
     let parsed_url = url::Url::parse(url)?;
@@ -287,11 +325,7 @@ fn from_pdf(url: &str, config: &Config) -> Result<Paper, Fallacy> {
         return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
     }
 
-    // Initialize HTTP client.
-    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let client = reqwest::blocking::ClientBuilder::new()
-        .user_agent(user_agent)
-        .build()?;
+    let client = http_client()?;
 
     // Download PDF file.
     let mut cursor = Cursor::new(client.get(url).send()?.bytes()?);
@@ -333,7 +367,7 @@ fn from_pdf(url: &str, config: &Config) -> Result<Paper, Fallacy> {
     // know the title of the PDF.
     let filename = as_filename(&title);
     let filepath = make_unique_path(&config.storage.file_dir, &filename, ".pdf");
-    println!("Saving to {:?}.", filepath);
+    eprintln!("Saving to {:?}.", filepath);
     std::fs::rename(tmpfile.path(), &filepath)?;
 
     Ok(Paper {
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 use std::process::Command;
 
 use crate::cmd::prelude::*;
+use crate::paper::Event;
 use crate::utils::confirm;
 
 pub static MAN: &str = include_str!("../../man/ed.md");
@@ -17,7 +18,7 @@ pub fn execute(
         Some(list) => list.0,
         // Papers are specified as filter.
         None => {
-            match crate::cmd::ls::execute(input, state, config)? {
+            match crate::cmd::ls::filter(input, state, config)? {
                 CommandOutput::Papers(paper_list) => paper_list.0,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!("internal ls invocation returned wrong output variant"),
@@ -25,39 +26,54 @@ pub fn execute(
         }
     };
 
-    // Build a vector of note paths.
-    let num_papers = selected.len();
+    // Build a vector of note paths, skipping papers that no longer exist.
+    let mut ids = Vec::new();
     let mut notes = Vec::new();
-    for i in selected {
-        notes.push(state.papers[i].notepath(config, true)?.unwrap());
+    for id in selected {
+        if let Some(paper) = state.paper_mut(id) {
+            notes.push(paper.notepath(config, true)?.unwrap());
+            ids.push(id);
+        }
     }
+    let num_papers = ids.len();
 
     // Ask for confirmation.
     if num_papers > 1 {
         confirm(format!("Open notes for {} paper?", num_papers), true)?;
     }
 
-    // Open notes.
+    // Open notes, and record when they were opened.
     if config.output.editor_batch {
-        spawn(build_editor_command(notes.as_ref(), config), true);
+        if spawn(build_editor_command(notes.as_ref(), config), true) {
+            for &id in ids.iter() {
+                if let Some(paper) = state.paper_mut(id) {
+                    paper.record(Event::Read);
+                }
+            }
+        }
     } else {
-        for note in notes {
-            spawn(build_editor_command(&[note], config), false);
+        for (&id, note) in ids.iter().zip(notes) {
+            if spawn(build_editor_command(&[note], config), false) {
+                if let Some(paper) = state.paper_mut(id) {
+                    paper.record(Event::Read);
+                }
+            }
         }
     }
 
     Ok(CommandOutput::None)
 }
 
-fn spawn(mut command: Command, block: bool) {
+/// Returns whether the editor was launched.
+fn spawn(mut command: Command, block: bool) -> bool {
     match command.spawn() {
         Ok(mut handle) => {
-            if !block {
-                return;
-            }
-            if let Err(e) = handle.wait() {
-                println!("Failed to wait subprocess: {}", e);
+            if block {
+                if let Err(e) = handle.wait() {
+                    println!("Failed to wait subprocess: {}", e);
+                }
             }
+            true
         }
         Err(e) => {
             if matches!(e.kind(), std::io::ErrorKind::NotFound) {
@@ -65,6 +81,7 @@ fn spawn(mut command: Command, block: bool) {
             } else {
                 println!("Failed to spawn subprocess: '{:?}'", e);
             }
+            false
         }
     }
 }
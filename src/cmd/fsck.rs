@@ -0,0 +1,442 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::cmd::prelude::*;
+use crate::paper::Paper;
+use crate::utils::{ask_for, confirm, make_unique_path};
+
+pub static MAN: &str = include_str!("../../man/fsck.md");
+
+/// Whether and how problems are repaired.
+#[derive(PartialEq)]
+enum Mode {
+    Report,
+    Interactive,
+    Automatic,
+}
+
+/// Which storage directory a path is relative to.
+#[derive(Clone, Copy)]
+enum Kind {
+    File,
+    Note,
+}
+
+/// A problem found in the paperbase.
+enum Problem {
+    /// The file or note of the paper does not exist.
+    Missing(Kind, u64, PathBuf),
+    /// No paper refers to the file or note.
+    Orphan(Kind, PathBuf),
+    /// The paper shares its file or note with an earlier paper.
+    Shared(Kind, u64, PathBuf),
+    /// The paper refers to a file or note outside the storage directory.
+    Outside(Kind, u64, PathBuf),
+    /// The paper has no authors.
+    NoAuthors(u64),
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Note => "note",
+        }
+    }
+
+    fn dir(self, config: &Config) -> &Path {
+        match self {
+            Self::File => &config.storage.file_dir,
+            Self::Note => &config.storage.note_dir,
+        }
+    }
+
+    fn path(self, paper: &Paper) -> Option<&PathBuf> {
+        match self {
+            Self::File => paper.filepath.as_ref(),
+            Self::Note => paper.notepath.as_ref(),
+        }
+    }
+
+    fn path_mut(self, paper: &mut Paper) -> &mut Option<PathBuf> {
+        match self {
+            Self::File => &mut paper.filepath,
+            Self::Note => &mut paper.notepath,
+        }
+    }
+
+    /// Extension of files of this kind in the storage directory.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::File => "pdf",
+            Self::Note => "md",
+        }
+    }
+}
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let mode = match input.args.get(1).map(|s| s.as_str()) {
+        None => Mode::Report,
+        Some("-i") => Mode::Interactive,
+        Some("-y") => Mode::Automatic,
+        Some(option) => return Err(Fallacy::FsckUnknownOption(option.to_owned())),
+    };
+
+    let problems = check(state, config)?;
+    if problems.is_empty() {
+        return Ok(CommandOutput::Message(format!(
+            "No problems found in {} {}.\n",
+            state.papers.len(),
+            if state.papers.len() != 1 {
+                "papers"
+            } else {
+                "paper"
+            },
+        )));
+    }
+
+    let mut repaired = 0;
+    for problem in problems.iter() {
+        eprintln!("{}", problem.describe(state));
+        let repair = match (&mode, problem) {
+            (Mode::Report, _) => continue,
+            // Authors cannot be guessed.
+            (Mode::Automatic, Problem::NoAuthors(_)) => {
+                eprintln!("  Needs manual repair.");
+                continue;
+            }
+            // The file may just not be synced yet, so only clear the path
+            // when asked to.
+            (Mode::Automatic, Problem::Missing(..)) => {
+                eprintln!("  Skipped. Run `fsck -i` to clear the path.");
+                continue;
+            }
+            (Mode::Interactive, Problem::NoAuthors(id)) => {
+                let authors = ask_for("  Authors, separated by commas", None)?;
+                if authors.is_empty() {
+                    continue;
+                }
+                if let Some(paper) = state.paper_mut(*id) {
+                    paper.authors = authors.split(',').map(|s| s.trim().to_owned()).collect();
+                }
+                repaired += 1;
+                continue;
+            }
+            (Mode::Interactive, problem) => {
+                match confirm(format!("  {}?", problem.repair_description()), false) {
+                    Ok(()) => true,
+                    Err(Fallacy::FailedUserInteraction(_)) => false,
+                    Err(e) => return Err(e),
+                }
+            }
+            (Mode::Automatic, _) => true,
+        };
+        if repair {
+            match problem.repair(state, config) {
+                Ok(()) => {
+                    if mode == Mode::Automatic {
+                        eprintln!("  {}.", problem.repair_description());
+                    }
+                    repaired += 1;
+                }
+                Err(e) => eprintln!("  Failed to repair: {}", e),
+            }
+        }
+    }
+
+    let found = format!(
+        "Found {} {}.",
+        problems.len(),
+        if problems.len() != 1 {
+            "problems"
+        } else {
+            "problem"
+        },
+    );
+    Ok(CommandOutput::Message(match mode {
+        Mode::Report => format!(
+            "{} Run `fsck -i` to repair interactively, or `fsck -y` to repair automatically.\n",
+            found
+        ),
+        _ => format!("{} Repaired {}.\n", found, repaired),
+    }))
+}
+
+/// Find problems in the paperbase.
+fn check(state: &State, config: &Config) -> Result<Vec<Problem>, Fallacy> {
+    let mut problems = Vec::new();
+    for &kind in [Kind::File, Kind::Note].iter() {
+        let dir = kind.dir(config);
+        let mut referenced = HashSet::new();
+        for paper in state.papers.iter() {
+            let path = match kind.path(paper) {
+                Some(path) => path,
+                None => continue,
+            };
+            let full = dir.join(path);
+            if !full.is_file() {
+                problems.push(Problem::Missing(kind, paper.id, path.clone()));
+                continue;
+            }
+            let full = canonical(&full);
+            if referenced.contains(&full) {
+                problems.push(Problem::Shared(kind, paper.id, path.clone()));
+            } else if !full.starts_with(canonical(dir)) {
+                problems.push(Problem::Outside(kind, paper.id, path.clone()));
+            }
+            referenced.insert(full);
+        }
+
+        // Directories inside the storage directory that are not ours to check.
+        let skipped = [
+            config.storage.trash_dir(),
+            config.storage.orphan_dir(),
+            config.storage.backup_dir(),
+            config.storage.note_dir.join("book"),
+        ];
+        let mut files = Vec::new();
+        walk(dir, kind.extension(), &skipped, &mut files)?;
+        files.sort();
+        for file in files {
+            if !referenced.contains(&canonical(&file)) {
+                problems.push(Problem::Orphan(kind, file));
+            }
+        }
+    }
+
+    for paper in state.papers.iter() {
+        if paper.authors.iter().all(|a| a.trim().is_empty()) {
+            problems.push(Problem::NoAuthors(paper.id));
+        }
+    }
+    Ok(problems)
+}
+
+/// Collect files with the extension under the directory, except those
+/// under `skipped` directories.
+fn walk(
+    dir: &Path,
+    ext: &str,
+    skipped: &[PathBuf],
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Fallacy> {
+    if !dir.is_dir() || skipped.iter().any(|s| s == dir) {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, ext, skipped, files)?;
+        } else if path.extension().is_some_and(|e| e == ext) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+impl Problem {
+    fn describe(&self, state: &State) -> String {
+        let paper = |id: u64| match state.paper(id) {
+            Some(paper) => format!("#{} '{}'", id, paper.title),
+            None => format!("#{}", id),
+        };
+        match self {
+            Self::Missing(kind, id, path) => {
+                format!("{}: {} {:?} does not exist.", paper(*id), kind.name(), path)
+            }
+            Self::Orphan(kind, path) => {
+                format!("No paper refers to the {} {:?}.", kind.name(), path)
+            }
+            Self::Shared(kind, id, path) => format!(
+                "{}: {} {:?} is shared with another paper.",
+                paper(*id),
+                kind.name(),
+                path
+            ),
+            Self::Outside(kind, id, path) => format!(
+                "{}: {} {:?} is outside the {} directory.",
+                paper(*id),
+                kind.name(),
+                path,
+                kind.name()
+            ),
+            Self::NoAuthors(id) => format!("{}: has no authors.", paper(*id)),
+        }
+    }
+
+    fn repair_description(&self) -> String {
+        match self {
+            Self::Missing(kind, ..) => format!("Clear the {} path", kind.name()),
+            Self::Orphan(kind, _) => format!("Move the {} to the orphans directory", kind.name()),
+            Self::Shared(kind, ..) => format!("Give the paper its own copy of the {}", kind.name()),
+            Self::Outside(kind, ..) => {
+                format!("Copy the {0} into the {0} directory", kind.name())
+            }
+            Self::NoAuthors(_) => "Set the authors".to_owned(),
+        }
+    }
+
+    fn repair(&self, state: &mut State, config: &Config) -> Result<(), Fallacy> {
+        match self {
+            Self::Missing(kind, id, _) => {
+                if let Some(paper) = state.paper_mut(*id) {
+                    *kind.path_mut(paper) = None;
+                }
+            }
+            Self::Orphan(_, path) => {
+                let dir = config.storage.orphan_dir();
+                std::fs::create_dir_all(&dir)?;
+                state.undo.move_file(path, &unique_path(&dir, path))?;
+            }
+            Self::Shared(kind, id, path) | Self::Outside(kind, id, path) => {
+                // Copy next to the shared file, or into the storage directory.
+                let dir = kind.dir(config);
+                let from = dir.join(path);
+                let to_dir = match self {
+                    Self::Shared(..) => from.parent().unwrap_or(dir),
+                    _ => dir,
+                };
+                let to = unique_path(to_dir, &from);
+                std::fs::copy(&from, &to)?;
+                let relative = to.strip_prefix(dir).unwrap_or(&to).to_owned();
+                if let Some(paper) = state.paper_mut(*id) {
+                    *kind.path_mut(paper) = Some(relative);
+                }
+            }
+            // Repaired interactively.
+            Self::NoAuthors(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// A path in the directory named like the file, but not taken yet.
+fn unique_path(dir: &Path, file: &Path) -> PathBuf {
+    let name = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = file
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    make_unique_path(dir, &name, &ext)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::paper;
+
+    fn summary(problems: &[Problem]) -> Vec<(&'static str, Option<u64>)> {
+        problems
+            .iter()
+            .map(|problem| match problem {
+                Problem::Missing(_, id, _) => ("missing", Some(*id)),
+                Problem::Orphan(..) => ("orphan", None),
+                Problem::Shared(_, id, _) => ("shared", Some(*id)),
+                Problem::Outside(_, id, _) => ("outside", Some(*id)),
+                Problem::NoAuthors(id) => ("no authors", Some(*id)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_and_repair() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let mut config = Config::default();
+        config.storage.file_dir = root.join("files");
+        config.storage.note_dir = root.join("notes");
+        config.storage.trash_dir = Some(root.join("trash"));
+        config.storage.backup_dir = Some(root.join("backups"));
+        for dir in ["files", "notes", "outside"].iter() {
+            std::fs::create_dir(root.join(dir)).unwrap();
+        }
+        for file in ["files/a.pdf", "files/orphan.pdf", "outside/b.pdf"].iter() {
+            std::fs::write(root.join(file), file).unwrap();
+        }
+
+        let mut one = paper(1, "One").by(&["A"]);
+        one.filepath = Some(PathBuf::from("a.pdf"));
+        one.notepath = Some(PathBuf::from("one.md"));
+        let mut two = paper(2, "Two").by(&["B"]);
+        two.filepath = Some(PathBuf::from("a.pdf"));
+        let mut three = paper(3, "Three");
+        three.filepath = Some(root.join("outside/b.pdf"));
+        let mut state = State::default();
+        state.papers = vec![one, two, three];
+
+        let problems = check(&state, &config).unwrap();
+        assert_eq!(
+            summary(&problems),
+            vec![
+                ("shared", Some(2)),
+                ("outside", Some(3)),
+                ("orphan", None),
+                ("missing", Some(1)),
+                ("no authors", Some(3)),
+            ]
+        );
+
+        for problem in problems.iter() {
+            problem.repair(&mut state, &config).unwrap();
+        }
+        assert_eq!(
+            summary(&check(&state, &config).unwrap()),
+            vec![("no authors", Some(3))]
+        );
+        let paths: Vec<_> = state.papers.iter().map(|p| p.filepath.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Some(PathBuf::from("a.pdf")),
+                Some(PathBuf::from("a-1.pdf")),
+                Some(PathBuf::from("b.pdf")),
+            ]
+        );
+        assert_eq!(state.papers[0].notepath, None);
+        assert!(!root.join("files/orphan.pdf").exists());
+        assert!(root.join("orphans/orphan.pdf").exists());
+        assert!(!root.join("trash").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn outside_resolves_links() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let mut config = Config::default();
+        config.storage.file_dir = root.join("library");
+        config.storage.note_dir = root.join("notes");
+        for dir in ["files", "outside"].iter() {
+            std::fs::create_dir(root.join(dir)).unwrap();
+        }
+        std::os::unix::fs::symlink(root.join("files"), root.join("library")).unwrap();
+        for file in ["files/a.pdf", "outside/b.pdf"].iter() {
+            std::fs::write(root.join(file), file).unwrap();
+        }
+
+        // Inside the storage directory through the link.
+        let mut one = paper(1, "One").by(&["A"]);
+        one.filepath = Some(root.join("files/a.pdf"));
+        // Outside it, though the path is relative.
+        let mut two = paper(2, "Two").by(&["B"]);
+        two.filepath = Some(PathBuf::from("../outside/b.pdf"));
+        let mut state = State::default();
+        state.papers = vec![one, two];
+
+        assert_eq!(
+            summary(&check(&state, &config).unwrap()),
+            vec![("outside", Some(2))]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use regex::RegexBuilder;
+
+use crate::cmd::prelude::*;
+use crate::paper::PaperList;
+
+pub static MAN: &str = include_str!("../../man/grep.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Parse options. The first non-option argument is the pattern.
+    let mut print_lines = false;
+    let mut pattern = None;
+    let mut filter_args = vec![input.args[0].clone()];
+    for arg in input.args.drain(1..) {
+        if pattern.is_none() && arg == "-n" {
+            print_lines = true;
+        } else if pattern.is_none() {
+            pattern = Some(arg);
+        } else {
+            filter_args.push(arg);
+        }
+    }
+    let pattern = match pattern {
+        Some(pattern) => pattern,
+        None => return Err(Fallacy::GrepNoPattern),
+    };
+    let regex = match RegexBuilder::new(&pattern)
+        .case_insensitive(config.filter.case_insensitive_regex)
+        .build()
+    {
+        Ok(regex) => regex,
+        Err(e) => return Err(Fallacy::FilterBuildFailed(e)),
+    };
+
+    // Build paper list from input.
+    let selected = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list.0,
+        // Papers are specified as filter.
+        None => {
+            input.args = filter_args;
+            match crate::cmd::ls::filter(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list.0,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!("ls did not return CommandOutput::Papers."),
+            }
+        }
+    };
+
+    // Search notes. A note that cannot be read does not stop the search.
+    let mut matched = Vec::new();
+    for id in selected {
+        let notepath = match state.paper(id).and_then(|p| p.notepath.as_ref()) {
+            Some(notepath) => config.storage.note_dir.join(notepath),
+            None => continue,
+        };
+        let content = match std::fs::read_to_string(&notepath) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                eprintln!("{}", Fallacy::GrepReadFailed(notepath, e));
+                continue;
+            }
+        };
+
+        let mut is_match = false;
+        for (lineno, line) in content.lines().enumerate() {
+            if regex.is_match(line) {
+                is_match = true;
+                if !print_lines {
+                    break;
+                }
+                // `notepath` was built from a file name, so `unwrap` will not panic.
+                eprintln!(
+                    "{}:{}: {}",
+                    notepath.file_name().unwrap().to_string_lossy(),
+                    lineno + 1,
+                    line
+                );
+            }
+        }
+        if is_match {
+            matched.push(id);
+        }
+    }
+
+    Ok(CommandOutput::Papers(PaperList(matched)))
+}
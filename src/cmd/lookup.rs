@@ -0,0 +1,105 @@
+use crate::cmd::prelude::*;
+use crate::dblp::{self, DblpRecord};
+use crate::paper::{Event, Paper, PaperList};
+use crate::utils::{confirm, http_client};
+
+pub static MAN: &str = include_str!("../../man/lookup.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Build paper list from input.
+    let selected = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list.0,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::filter(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list.0,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let client = http_client()?;
+
+    let mut changed = Vec::new();
+    for id in selected {
+        let paper = match state.paper(id) {
+            Some(paper) => paper,
+            None => continue,
+        };
+        eprintln!("#{} '{}'", id, paper.title);
+        let records = match dblp::search(&client, config.sources.dblp_url(), &paper.title) {
+            Ok(records) => records,
+            Err(e) => {
+                eprintln!("  {}", e);
+                continue;
+            }
+        };
+
+        // Prefer the published version over preprints.
+        let record = records
+            .iter()
+            .filter(|r| dblp::same_title(&r.title, &paper.title))
+            .min_by_key(|r| r.is_informal());
+        let record = match record {
+            Some(record) => record,
+            None => {
+                eprintln!("  Not found on DBLP.");
+                continue;
+            }
+        };
+
+        let proposed = propose(paper, record);
+        if proposed == *paper {
+            eprintln!("  Already matches DBLP.");
+            continue;
+        }
+        for (field, before, after) in [
+            ("venue", &paper.venue, &proposed.venue),
+            ("year", &paper.year, &proposed.year),
+            (
+                "authors",
+                &paper.authors.join(", "),
+                &proposed.authors.join(", "),
+            ),
+        ] {
+            if before != after {
+                eprintln!("  {}: '{}' -> '{}'", field, before, after);
+            }
+        }
+        match confirm(format!("  Apply changes from {}?", record.key), false) {
+            Ok(()) => {
+                if let Some(paper) = state.paper_mut(id) {
+                    *paper = proposed;
+                    paper.record(Event::Modified);
+                    changed.push(id);
+                }
+            }
+            Err(Fallacy::FailedUserInteraction(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(CommandOutput::Papers(PaperList(changed)))
+}
+
+/// The paper with the venue, year, and author spellings of the record.
+/// Empty values in the record are not proposed.
+fn propose(paper: &Paper, record: &DblpRecord) -> Paper {
+    let mut proposed = paper.clone();
+    if !record.venue.is_empty() {
+        proposed.venue = record.venue.clone();
+    }
+    if !record.year.is_empty() {
+        proposed.year = record.year.clone();
+    }
+    if !record.authors.is_empty() {
+        proposed.authors = record.authors.clone();
+    }
+    proposed
+}
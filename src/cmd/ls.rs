@@ -1,10 +1,38 @@
 use crate::cmd::prelude::*;
-use crate::paper::PaperList;
+use crate::paper::{PaperList, SortKey};
 use crate::state::FilterInst;
 
 pub static MAN: &str = include_str!("../../man/ls.md");
 
 pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Parse the output format, which comes before the filter.
+    let mut format = None;
+    if input.args.get(1).map(|s| s.as_str()) == Some("-f") {
+        match input.args.get(2) {
+            Some(name) => format = Some(name.parse()?),
+            None => return Err(Fallacy::LsNoFormat),
+        }
+        input.args.drain(1..3);
+    }
+
+    match filter(input, state, config)? {
+        CommandOutput::Papers(paper_list) => match format {
+            Some(format) => Ok(CommandOutput::Formatted(paper_list, format)),
+            None => Ok(CommandOutput::Papers(paper_list)),
+        },
+        // `filter` always returns CommandOutput::Papers.
+        _ => panic!("ls did not return CommandOutput::Papers."),
+    }
+}
+
+/// Select papers that match the filter in the arguments and the current
+/// filter state, sorted with the default sort keys. Other commands call this
+/// to build their paper list when nothing is given through pipe.
+pub fn filter(
     input: CommandInput,
     state: &mut State,
     config: &Config,
@@ -23,16 +51,48 @@ pub fn execute(
     let mut selected = Vec::new();
     // Shortcut path for listing all papers.
     if filter.is_empty() {
-        selected = (0..state.papers.len()).collect();
+        selected = state.ids();
     }
-    // Our filter is not empty.
+    // Let the database run the filter if papers are stored in one.
+    else if let Some(ids) = state.query(&filter, &config.storage) {
+        selected = ids;
+    }
+    // Filter papers in memory.
     else {
-        for (ind, paper) in state.papers.iter().enumerate() {
+        for paper in state.papers.iter() {
             if filter.matches(paper) {
-                selected.push(ind);
+                selected.push(paper.id);
             }
         }
     }
 
-    Ok(CommandOutput::Papers(PaperList(selected)))
+    // Papers whose year is not a number cannot be compared with years.
+    // Report those the filter could not decide on, instead of silently
+    // leaving them out.
+    let unparsed: Vec<_> = state
+        .papers
+        .iter()
+        .filter(|p| filter.undecided(p))
+        .map(|p| format!("'{}' (year '{}')", p.title, p.year))
+        .collect();
+    if !unparsed.is_empty() {
+        eprintln!(
+            "Years of {} {} are not numbers and cannot be compared: {}",
+            unparsed.len(),
+            if unparsed.len() != 1 {
+                "papers"
+            } else {
+                "paper"
+            },
+            unparsed.join(", ")
+        );
+    }
+
+    // Sort papers with the default sort keys.
+    let mut paper_list = PaperList(selected);
+    if let Some(sort) = &config.output.sort {
+        paper_list.sort(state, &SortKey::parse_list(&sort.join(","))?);
+    }
+
+    Ok(CommandOutput::Papers(paper_list))
 }
@@ -16,18 +16,30 @@ pub fn execute(
     let entry = input.args[1].as_ref();
     let man_str = match entry {
         "command" => crate::cmd::MAN,
+        "bib" => crate::cmd::bib::MAN,
         "cd" => crate::cmd::cd::MAN,
+        "convert" => crate::cmd::convert::MAN,
         "curl" => crate::cmd::curl::MAN,
         "ed" => crate::cmd::ed::MAN,
         "exit" => crate::cmd::exit::MAN,
+        "fsck" => crate::cmd::fsck::MAN,
+        "grep" => crate::cmd::grep::MAN,
+        "lookup" => crate::cmd::lookup::MAN,
         "ls" => crate::cmd::ls::MAN,
         "man" => crate::cmd::man::MAN,
         "open" => crate::cmd::open::MAN,
         "printf" => crate::cmd::printf::MAN,
         "pwd" => crate::cmd::pwd::MAN,
+        "redo" => crate::cmd::redo::MAN,
+        "restore" => crate::cmd::restore::MAN,
         "rm" => crate::cmd::rm::MAN,
         "set" => crate::cmd::set::MAN,
+        "sort" => crate::cmd::sort::MAN,
+        "stat" => crate::cmd::stat::MAN,
+        "top" => crate::cmd::top::MAN,
         "touch" => crate::cmd::touch::MAN,
+        "trash" => crate::cmd::trash::MAN,
+        "undo" => crate::cmd::undo::MAN,
         "wc" => crate::cmd::wc::MAN,
         "config" => crate::config::MAN,
         "filter" => crate::filter::MAN,
@@ -1,21 +1,33 @@
-use crate::config::Config;
+use crate::config::{Config, OutputFormat};
 use crate::error::Fallacy;
 use crate::paper::PaperList;
 use crate::state::State;
 
+mod bib;
 mod cd;
+mod convert;
 mod curl;
 mod ed;
 mod exit;
+mod fsck;
+mod grep;
+mod lookup;
 mod ls;
 mod man;
 mod open;
 pub mod prelude;
 mod printf;
 mod pwd;
+mod redo;
+mod restore;
 mod rm;
 mod set;
+mod sort;
+mod stat;
+mod top;
 mod touch;
+mod trash;
+mod undo;
 mod wc;
 
 pub static MAN: &str = include_str!("../../man/command.md");
@@ -30,6 +42,8 @@ pub struct CommandInput {
 pub enum CommandOutput {
     None,
     Papers(PaperList),
+    /// Papers to be printed in a specific format, regardless of the config.
+    Formatted(PaperList, OutputFormat),
     Message(String),
 }
 
@@ -39,6 +53,7 @@ impl CommandInput {
             CommandOutput::None => None,
             CommandOutput::Message(_) => None,
             CommandOutput::Papers(p) => Some(p),
+            CommandOutput::Formatted(p, _) => Some(p),
         };
         Self { args, papers }
     }
@@ -49,25 +64,56 @@ impl CommandOutput {
         match self {
             CommandOutput::None => "".to_string(),
             CommandOutput::Message(s) => s,
-            CommandOutput::Papers(p) => p.into_string(state, config),
+            CommandOutput::Papers(p) => {
+                let format = config.output.format.unwrap_or(OutputFormat::Table);
+                p.into_string(state, config, format)
+            }
+            CommandOutput::Formatted(p, format) => p.into_string(state, config, format),
         }
     }
 }
 
+/// Names of all commands, in the order of `to_executor`.
+pub static COMMANDS: &[&str] = &[
+    "bib", "cd", "convert", "curl", "exit", "fsck", "grep", "lookup", "ls", "man", "open",
+    "printf", "pwd", "ed", "redo", "restore", "rm", "set", "sort", "stat", "top", "touch", "trash",
+    "undo", "wc",
+];
+
+/// Commands that never change papers or the trash.
+pub static READ_ONLY_COMMANDS: &[&str] = &[
+    "cd", "convert", "exit", "grep", "ls", "man", "pwd", "sort", "stat", "top", "wc",
+];
+
+/// Manual subjects other than commands. See `man man`.
+pub static MAN_SUBJECTS: &[&str] = &["command", "config", "filter", "paper"];
+
 pub fn to_executor(command: String) -> Result<ExecuteFn, Fallacy> {
     match command.as_ref() {
+        "bib" => Ok(bib::execute),
         "cd" => Ok(cd::execute),
+        "convert" => Ok(convert::execute),
         "curl" => Ok(curl::execute),
         "exit" => Ok(exit::execute),
+        "fsck" => Ok(fsck::execute),
+        "grep" => Ok(grep::execute),
+        "lookup" => Ok(lookup::execute),
         "ls" => Ok(ls::execute),
         "man" => Ok(man::execute),
         "open" => Ok(open::execute),
         "printf" => Ok(printf::execute),
         "pwd" => Ok(pwd::execute),
         "ed" => Ok(ed::execute),
+        "redo" => Ok(redo::execute),
+        "restore" => Ok(restore::execute),
         "rm" => Ok(rm::execute),
         "set" => Ok(set::execute),
+        "sort" => Ok(sort::execute),
+        "stat" => Ok(stat::execute),
+        "top" => Ok(top::execute),
         "touch" => Ok(touch::execute),
+        "trash" => Ok(trash::execute),
+        "undo" => Ok(undo::execute),
         "wc" => Ok(wc::execute),
         _ => Err(Fallacy::UnknownCommand(command.to_owned())),
     }
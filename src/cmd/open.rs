@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
 use crate::cmd::prelude::*;
-use crate::paper::PaperList;
+use crate::paper::{Event, PaperList};
 use crate::utils::confirm;
 
 pub static MAN: &str = include_str!("../../man/open.md");
@@ -18,7 +18,7 @@ pub fn execute(
         Some(list) => list.0,
         // Papers are specified as filter.
         None => {
-            match crate::cmd::ls::execute(input, state, config)? {
+            match crate::cmd::ls::filter(input, state, config)? {
                 CommandOutput::Papers(paper_list) => paper_list.0,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!(),
@@ -28,13 +28,9 @@ pub fn execute(
 
     // Build a vector of file paths.
     let num_papers = selected.len();
-    let selected: Vec<_> = selected
-        .into_iter()
-        .filter(|&i| state.papers[i].filepath.is_some())
-        .collect();
     let mut files = Vec::new();
-    for &ind in selected.iter() {
-        if let Some(path) = state.papers[ind].filepath(config) {
+    for ind in selected {
+        if let Some(path) = state.paper(ind).and_then(|p| p.filepath(config)) {
             files.push((ind, path));
         }
     }
@@ -56,12 +52,12 @@ pub fn execute(
     }
 
     // Open papers.
-    if config.output.viewer_batch {
-        let (selected, files): (Vec<usize>, Vec<PathBuf>) = files.into_iter().unzip();
+    let opened = if config.output.viewer_batch {
+        let (selected, files): (Vec<u64>, Vec<PathBuf>) = files.into_iter().unzip();
         if spawn(build_viewer_command(files.as_ref(), config)) {
-            Ok(CommandOutput::Papers(PaperList(selected)))
+            selected
         } else {
-            Ok(CommandOutput::None)
+            return Ok(CommandOutput::None);
         }
     } else {
         let mut selected = Vec::new();
@@ -70,8 +66,16 @@ pub fn execute(
                 selected.push(i);
             }
         }
-        Ok(CommandOutput::Papers(PaperList(selected)))
+        selected
+    };
+
+    // Record when papers were opened.
+    for &id in opened.iter() {
+        if let Some(paper) = state.paper_mut(id) {
+            paper.record(Event::Opened);
+        }
     }
+    Ok(CommandOutput::Papers(PaperList(opened)))
 }
 
 fn spawn(mut command: Command) -> bool {
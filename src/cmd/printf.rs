@@ -21,7 +21,7 @@ pub fn execute(
         Some(list) => list.0,
         // Papers are specified as filter.
         None => {
-            match crate::cmd::ls::execute(input, state, config)? {
+            match crate::cmd::ls::filter(input, state, config)? {
                 CommandOutput::Papers(paper_list) => paper_list.0,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!("ls did not return CommandOutput::Papers."),
@@ -48,7 +48,10 @@ pub fn execute(
     let mut formatted = Vec::new();
     let mut summary = Summary::default();
     for (sec, idx) in selected.into_iter().enumerate() {
-        let p = &mut state.papers[idx];
+        let p = match state.paper_mut(idx) {
+            Some(p) => p,
+            None => continue,
+        };
         let notepath = p.notepath(config, true)?.unwrap();
         summary.numbered_chapters.push(SummaryItem::Link(Link {
             name: p.title.clone(),
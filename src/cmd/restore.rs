@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use crate::cmd::prelude::*;
+use crate::state::backup;
+use crate::utils::confirm;
+
+pub static MAN: &str = include_str!("../../man/restore.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let backups = backup::list(&config.storage)?;
+    if backups.is_empty() {
+        return Err(Fallacy::RestoreNoBackups(config.storage.backup_dir()));
+    }
+
+    // Without an argument, list backups.
+    let choice = match input.args.get(1) {
+        Some(choice) => choice,
+        None => return Ok(CommandOutput::Message(listing(&backups))),
+    };
+
+    // Restore the chosen backup.
+    let path = match choice.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= backups.len() => &backups[n - 1],
+        _ => return Err(Fallacy::RestoreInvalidBackup(choice.to_owned())),
+    };
+    let restored = State::read(path)?;
+    confirm(
+        format!(
+            "Replace {} papers with {} papers from the backup at {}?",
+            state.papers.len(),
+            restored.len(),
+            backup::timestamp(path).unwrap_or_default(),
+        ),
+        false,
+    )?;
+    state.papers = restored;
+    state.assign_ids();
+    state.request_backup();
+
+    Ok(CommandOutput::Message(format!(
+        "Restored {} {}. The previous paperbase is kept as the latest backup.\n",
+        state.papers.len(),
+        if state.papers.len() != 1 {
+            "papers"
+        } else {
+            "paper"
+        },
+    )))
+}
+
+/// One line per backup, with the number of papers in it. A backup that
+/// cannot be read is still listed, so that the others can be restored.
+fn listing(backups: &[PathBuf]) -> String {
+    let mut list = String::new();
+    for (i, path) in backups.iter().enumerate() {
+        let papers = match State::read(path) {
+            Ok(papers) if papers.len() != 1 => format!("{} papers", papers.len()),
+            Ok(_) => "1 paper".to_owned(),
+            Err(_) => "unreadable".to_owned(),
+        };
+        list.push_str(&format!(
+            "{:>3}) {} ({})\n",
+            i + 1,
+            backup::timestamp(path).unwrap_or_default(),
+            papers,
+        ));
+    }
+    list
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::paper;
+
+    #[test]
+    fn list_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("metadata-20210903-142031.512.yaml");
+        let bad = dir.path().join("metadata-20210903-141802.000.yaml");
+        State::write(&good, &[paper(1, "Spark")], 2).unwrap();
+        std::fs::write(&bad, "papers: [").unwrap();
+
+        assert_eq!(
+            listing(&[good, bad]),
+            "  1) 2021-09-03 14:20:31 (1 paper)\n  2) 2021-09-03 14:18:02 (unreadable)\n"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::cmd::prelude::*;
+use crate::state::trash::{trashed_path, TrashEntry, TrashedFile};
 use crate::utils::confirm;
 
 pub static MAN: &str = include_str!("../../man/rm.md");
@@ -8,12 +9,12 @@ pub fn execute(
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
-    let mut paper_list = match input.papers {
+    let paper_list = match input.papers {
         // Papers are given through pipe.
         Some(list) => list,
         // Papers are specified as filter.
         None => {
-            match crate::cmd::ls::execute(input, state, config)? {
+            match crate::cmd::ls::filter(input, state, config)? {
                 CommandOutput::Papers(paper_list) => paper_list,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!(),
@@ -25,42 +26,47 @@ pub fn execute(
     let num_paper = paper_list.0.len();
     if num_paper > 1 {
         confirm(
-            format!("Remove {} papers, including files and notes?", num_paper),
+            format!(
+                "Move {} papers to the trash, including files and notes?",
+                num_paper
+            ),
             false,
         )?;
     }
 
-    // Remove files and notes.
+    // Move papers, files, and notes to the trash.
     let mut errors = Vec::new();
-    for &ind in paper_list.0.iter() {
-        if let Some(filepath) = state.papers[ind].filepath(config) {
-            if let Err(e) = std::fs::remove_file(&filepath) {
-                errors.push(e);
-            }
-        }
-        if let Some(notepath) = state.papers[ind].notepath(config, false)? {
-            if let Err(e) = std::fs::remove_file(&notepath) {
-                errors.push(e);
+    for id in paper_list.0 {
+        let mut paper = match state.remove(id) {
+            Some(paper) => paper,
+            // Already removed, e.g. listed twice.
+            None => continue,
+        };
+        let mut paths = Vec::new();
+        paths.extend(paper.filepath(config));
+        paths.extend(paper.notepath(config, false)?);
+
+        let mut entry = TrashEntry::new(paper);
+        for path in paths {
+            let trashed = trashed_path(&path, &config.storage);
+            match state.undo.move_file(&path, &trashed) {
+                Ok(()) => entry.files.push(TrashedFile { path, trashed }),
+                Err(e) => errors.push(e),
             }
         }
-    }
-
-    // Remove papers.
-    paper_list.0.reverse();
-    for ind in paper_list.0 {
-        state.papers.remove(ind);
+        state.trash.entries.push(entry);
     }
 
     // Print errors.
     if !errors.is_empty() {
-        println!("Errors occurred while deleting files and notes:");
+        eprintln!("Errors occurred while moving files and notes to the trash:");
         for e in errors {
-            println!("{}", e);
+            eprintln!("{}", e);
         }
     }
 
     Ok(CommandOutput::Message(format!(
-        "Removed {} {} from the paperbase.\n",
+        "Moved {} {} to the trash.\n",
         num_paper,
         if num_paper != 1 { "papers" } else { "paper" },
     )))
@@ -1,4 +1,5 @@
 use crate::cmd::prelude::*;
+use crate::paper::Event;
 
 pub static MAN: &str = include_str!("../../man/set.md");
 
@@ -12,9 +13,17 @@ pub fn execute(
         return Err(Fallacy::SetNoPapers);
     }
 
-    // Apply changes.
-    for &ind in input.papers.as_ref().unwrap().0.iter() {
-        state.papers[ind].apply_from_args(config, &input.args[1..])?;
+    // Apply changes, and record the time of papers that changed.
+    for &id in input.papers.as_ref().unwrap().0.iter() {
+        let paper = match state.paper_mut(id) {
+            Some(paper) => paper,
+            None => continue,
+        };
+        let before = paper.clone();
+        paper.apply_from_args(config, &input.args[1..])?;
+        if *paper != before {
+            paper.record(Event::Modified);
+        }
     }
 
     Ok(CommandOutput::Papers(input.papers.unwrap()))
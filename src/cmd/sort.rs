@@ -0,0 +1,39 @@
+use crate::cmd::prelude::*;
+use crate::paper::SortKey;
+
+pub static MAN: &str = include_str!("../../man/sort.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Parse sort keys. Fall back to the default sort keys in the config.
+    let spec = input.args.drain(1..).collect::<Vec<_>>().join(" ");
+    let keys = if !spec.trim().is_empty() {
+        SortKey::parse_list(&spec)?
+    } else {
+        match &config.output.sort {
+            Some(default) => SortKey::parse_list(&default.join(","))?,
+            None => return Err(Fallacy::SortNoKeys),
+        }
+    };
+
+    // Build paper list from input.
+    let mut paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Sort all papers that match the default filter.
+        None => {
+            match crate::cmd::ls::filter(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!("ls did not return CommandOutput::Papers."),
+            }
+        }
+    };
+
+    paper_list.sort(state, &keys);
+
+    Ok(CommandOutput::Papers(paper_list))
+}
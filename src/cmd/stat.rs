@@ -0,0 +1,144 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/stat.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Parse the number of note lines to preview.
+    let mut preview_lines = 10;
+    if input.args.get(1).map(|s| s.as_str()) == Some("-n") {
+        preview_lines = match input.args.get(2).map(|s| s.parse()) {
+            Some(Ok(num)) => num,
+            _ => return Err(Fallacy::StatInvalidLineCount),
+        };
+        input.args.drain(1..3);
+    }
+
+    // Build paper list from input.
+    let selected = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list.0,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::filter(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list.0,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!("ls did not return CommandOutput::Papers."),
+            }
+        }
+    };
+
+    // Format each paper.
+    let mut stats = Vec::with_capacity(selected.len());
+    for ind in selected {
+        let paper = match state.paper(ind) {
+            Some(paper) => paper,
+            None => continue,
+        };
+        let mut labels: Vec<_> = paper.labels.iter().map(|s| s.as_str()).collect();
+        labels.sort_unstable();
+
+        let mut stat = String::new();
+        // Writing to a `String` never fails.
+        writeln!(stat, "id:       {}", paper.id).unwrap();
+        writeln!(stat, "title:    {}", paper.title).unwrap();
+        writeln!(
+            stat,
+            "nickname: {}",
+            paper.nickname.as_deref().unwrap_or("")
+        )
+        .unwrap();
+        writeln!(stat, "authors:  {}", paper.authors.join(", ")).unwrap();
+        writeln!(stat, "venue:    {}", paper.venue).unwrap();
+        writeln!(stat, "year:     {}", paper.year).unwrap();
+        writeln!(stat, "labels:   {}", labels.join(", ")).unwrap();
+        for event in ["added", "modified", "opened", "read"].iter() {
+            let time = paper.field_as_string(event);
+            writeln!(stat, "{:<9} {}", format!("{}:", event), time).unwrap();
+        }
+        for (key, value) in paper.fields.iter() {
+            writeln!(stat, "{:<9} {}", format!("{}:", key), value).unwrap();
+        }
+
+        // Paper file.
+        match paper.filepath(config) {
+            Some(path) => writeln!(stat, "filepath: {}", describe_pdf(&path)).unwrap(),
+            None => writeln!(stat, "filepath: none").unwrap(),
+        }
+
+        // Note file.
+        let notepath = paper
+            .notepath
+            .as_ref()
+            .map(|notepath| config.storage.note_dir.join(notepath));
+        match &notepath {
+            Some(path) => writeln!(stat, "notepath: {}", describe_file(path)).unwrap(),
+            None => writeln!(stat, "notepath: none").unwrap(),
+        }
+
+        // Note preview.
+        if let Some(path) = notepath.filter(|p| p.is_file()) {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    writeln!(stat, "--- note (first {} lines) ---", preview_lines).unwrap();
+                    for line in content.lines().take(preview_lines) {
+                        writeln!(stat, "{}", line).unwrap();
+                    }
+                }
+                Err(e) => writeln!(stat, "--- note unreadable: {} ---", e).unwrap(),
+            }
+        }
+
+        stats.push(stat);
+    }
+
+    Ok(CommandOutput::Message(stats.join("\n")))
+}
+
+/// Describe a file path with whether it exists and its size.
+fn describe_file(path: &Path) -> String {
+    match std::fs::metadata(path) {
+        Ok(meta) => format!("{} (exists, {})", path.display(), format_size(meta.len())),
+        Err(_) => format!("{} (missing)", path.display()),
+    }
+}
+
+/// Describe a PDF file path with whether it exists, its size, and its page count.
+fn describe_pdf(path: &Path) -> String {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return format!("{} (missing)", path.display()),
+    };
+    let pages = match pdf::file::File::open(path) {
+        Ok(pdf) => format!("{} pages", pdf.num_pages()),
+        Err(_) => "not a valid PDF".to_owned(),
+    };
+    format!(
+        "{} (exists, {}, {})",
+        path.display(),
+        format_size(meta.len()),
+        pages
+    )
+}
+
+/// Format a size in bytes in human-readable units.
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
+
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/top.md");
+
+/// How many authors to show in the author ranking.
+const NUM_TOP_AUTHORS: usize = 10;
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Build paper list from input.
+    let selected = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list.0,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::filter(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list.0,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!("ls did not return CommandOutput::Papers."),
+            }
+        }
+    };
+
+    // Aggregate, skipping papers that no longer exist.
+    let papers: Vec<_> = selected.iter().filter_map(|&id| state.paper(id)).collect();
+    let total = papers.len();
+    let mut venues = HashMap::new();
+    let mut years = HashMap::new();
+    let mut labels = HashMap::new();
+    let mut authors = HashMap::new();
+    let (mut missing_pdf, mut missing_note) = (0, 0);
+    let (mut done, mut active) = (0, 0);
+    for paper in papers {
+        *venues.entry(paper.venue.clone()).or_insert(0) += 1;
+        *years.entry(paper.year.clone()).or_insert(0) += 1;
+        for label in paper.labels.iter() {
+            *labels.entry(label.clone()).or_insert(0) += 1;
+        }
+        for author in paper.authors.iter() {
+            *authors.entry(author.clone()).or_insert(0) += 1;
+        }
+        if !paper.filepath(config).is_some_and(|p| p.is_file()) {
+            missing_pdf += 1;
+        }
+        let has_note = paper
+            .notepath
+            .as_ref()
+            .is_some_and(|p| config.storage.note_dir.join(p).is_file());
+        if !has_note {
+            missing_note += 1;
+        }
+        if paper.labels.contains("done") {
+            done += 1;
+        } else if paper.labels.contains("active") {
+            active += 1;
+        }
+    }
+
+    // Overview.
+    let mut tables = Vec::new();
+    let mut overview = new_table(&["", "papers"]);
+    overview.add_row(vec!["total".to_owned(), total.to_string()]);
+    overview.add_row(vec!["missing PDF".to_owned(), missing_pdf.to_string()]);
+    overview.add_row(vec!["missing note".to_owned(), missing_note.to_string()]);
+    tables.push(overview);
+
+    // Reading progress.
+    let mut progress = new_table(&["progress", "papers", "share"]);
+    for (name, count) in [
+        ("done", done),
+        ("active", active),
+        ("other", total - done - active),
+    ] {
+        progress.add_row(vec![
+            name.to_owned(),
+            count.to_string(),
+            percent(count, total),
+        ]);
+    }
+    tables.push(progress);
+
+    // Breakdowns. Years are shown most recent first, and everything else
+    // is shown most frequent first.
+    let mut years: Vec<_> = years.into_iter().collect();
+    years.sort_by(|a, b| b.0.cmp(&a.0));
+    tables.push(count_table("venue", by_count(venues), total, None));
+    tables.push(count_table("year", years, total, None));
+    tables.push(count_table("label", by_count(labels), total, None));
+    tables.push(count_table(
+        "author",
+        by_count(authors),
+        total,
+        Some(NUM_TOP_AUTHORS),
+    ));
+
+    Ok(CommandOutput::Message(
+        tables
+            .into_iter()
+            .map(|t| t.to_string() + "\n")
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ))
+}
+
+/// Create a table with the given header, in the same style as `ls`.
+fn new_table(header: &[&str]) -> Table {
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(header.iter().map(|s| {
+        Cell::new(s)
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold)
+    }));
+    table
+}
+
+/// Create a table of paper counts, optionally only showing the first `limit` rows.
+/// Shares are relative to the total number of papers.
+fn count_table(
+    name: &str,
+    counts: Vec<(String, usize)>,
+    total: usize,
+    limit: Option<usize>,
+) -> Table {
+    let mut table = new_table(&[name, "papers", "share"]);
+    let limit = limit.unwrap_or(counts.len());
+    for (key, count) in counts.into_iter().take(limit) {
+        table.add_row(vec![key, count.to_string(), percent(count, total)]);
+    }
+    table
+}
+
+/// Sort counts in decreasing order, breaking ties by name.
+fn by_count(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Format `count` as a percentage of `total`.
+fn percent(count: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_owned()
+    } else {
+        format!("{:.1}%", count as f64 / total as f64 * 100.0)
+    }
+}
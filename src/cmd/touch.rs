@@ -19,9 +19,7 @@ pub fn execute(
     }
 
     // Add paper to state.
-    state.papers.push(paper);
+    let id = state.add(paper);
 
-    Ok(CommandOutput::Papers(PaperList(vec![
-        state.papers.len() - 1,
-    ])))
+    Ok(CommandOutput::Papers(PaperList(vec![id])))
 }
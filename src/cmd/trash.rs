@@ -0,0 +1,172 @@
+use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
+
+use crate::cmd::prelude::*;
+use crate::filter::PaperFilter;
+use crate::paper::PaperList;
+use crate::state::trash::TrashEntry;
+use crate::utils::confirm;
+
+pub static MAN: &str = include_str!("../../man/trash.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let args = &input.args[1.min(input.args.len())..];
+    match args.first().map(|s| s.as_str()) {
+        None | Some("ls") => list(args.get(1..).unwrap_or_default(), state, config),
+        Some("restore") => restore(&args[1..], state, config),
+        Some("empty") => empty(&args[1..], state),
+        Some(subcommand) => Err(Fallacy::TrashUnknownSubcommand(subcommand.to_owned())),
+    }
+}
+
+/// Show removed papers that match the filter.
+fn list(args: &[String], state: &State, config: &Config) -> Result<CommandOutput, Fallacy> {
+    let filter = PaperFilter::from_args(args, config.filter.case_insensitive_regex)?;
+
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    let header = ["id", "title", "first author", "venue", "year", "removed"];
+    table.set_header(header.iter().map(|s| {
+        Cell::new(s)
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold)
+    }));
+    for entry in state.trash.entries.iter() {
+        if !filter.matches(&entry.paper) {
+            continue;
+        }
+        let mut row: Vec<_> = header[..5]
+            .iter()
+            .map(|col| entry.paper.field_as_string(col))
+            .collect();
+        row.push(
+            entry
+                .removed()
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+        );
+        table.add_row(row);
+    }
+
+    Ok(CommandOutput::Message(table.to_string() + "\n"))
+}
+
+/// Move removed papers that match the filter back to the paperbase,
+/// along with their files and notes.
+fn restore(args: &[String], state: &mut State, config: &Config) -> Result<CommandOutput, Fallacy> {
+    let filter = PaperFilter::from_args(args, config.filter.case_insensitive_regex)?;
+    if filter.is_empty() {
+        return Err(Fallacy::TrashNoFilter);
+    }
+
+    let (matched, kept) = std::mem::take(&mut state.trash.entries)
+        .into_iter()
+        .partition::<Vec<_>, _>(|entry| filter.matches(&entry.paper));
+    state.trash.entries = kept;
+
+    let mut restored = Vec::new();
+    let mut errors = Vec::new();
+    for entry in matched {
+        // Don't overwrite files created after the paper was removed.
+        if let Some(file) = entry.files.iter().find(|f| f.path.exists()) {
+            eprintln!(
+                "'{}' was not restored: {:?} already exists.",
+                entry.paper.title, file.path
+            );
+            state.trash.entries.push(entry);
+            continue;
+        }
+        // A paper whose files could not all be moved stays in the trash.
+        if let Err(e) = restore_files(&entry, state) {
+            errors.push(format!("'{}' was not restored: {}", entry.paper.title, e));
+            state.trash.entries.push(entry);
+            continue;
+        }
+        restored.push(state.insert(entry.paper));
+    }
+
+    // Print errors.
+    if !errors.is_empty() {
+        eprintln!("Errors occurred while restoring papers:");
+        for e in errors {
+            eprintln!("{}", e);
+        }
+    }
+
+    Ok(CommandOutput::Papers(PaperList(restored)))
+}
+
+/// Move the files of a removed paper back. If a file cannot be moved, the
+/// files moved so far are put back in the trash.
+fn restore_files(entry: &TrashEntry, state: &mut State) -> Result<(), Fallacy> {
+    for (ind, file) in entry.files.iter().enumerate() {
+        if let Err(e) = state.undo.move_file(&file.trashed, &file.path) {
+            for file in entry.files[..ind].iter().rev() {
+                if let Err(e) = state.undo.move_file(&file.path, &file.trashed) {
+                    eprintln!("Failed to move {:?} back to the trash: {}", file.path, e);
+                }
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Permanently delete removed papers, optionally only those removed more
+/// than the given number of days ago.
+fn empty(args: &[String], state: &mut State) -> Result<CommandOutput, Fallacy> {
+    let days = match args.first() {
+        Some(days) => match days.parse::<i64>() {
+            Ok(days) if days >= 0 => Some(days),
+            _ => return Err(Fallacy::TrashInvalidDays(days.to_owned())),
+        },
+        None => None,
+    };
+    let is_deleted = |entry: &TrashEntry| days.is_none_or(|days| entry.is_older_than(days));
+
+    // Ask for confirmation.
+    let num_paper = state.trash.entries.iter().filter(|e| is_deleted(e)).count();
+    if num_paper == 0 {
+        return Ok(CommandOutput::Message(
+            "Nothing in the trash to delete.\n".to_owned(),
+        ));
+    }
+    confirm(
+        format!(
+            "Permanently delete {} {}, including files and notes?",
+            num_paper,
+            if num_paper != 1 { "papers" } else { "paper" },
+        ),
+        false,
+    )?;
+
+    // Delete files and notes.
+    let mut errors = Vec::new();
+    for entry in state.trash.entries.iter().filter(|e| is_deleted(e)) {
+        for file in entry.files.iter() {
+            if let Err(e) = std::fs::remove_file(&file.trashed) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    errors.push(e);
+                }
+            }
+        }
+    }
+    state.trash.entries.retain(|e| !is_deleted(e));
+
+    // Print errors.
+    if !errors.is_empty() {
+        eprintln!("Errors occurred while deleting files and notes:");
+        for e in errors {
+            eprintln!("{}", e);
+        }
+    }
+
+    Ok(CommandOutput::Message(format!(
+        "Deleted {} {} permanently.\n",
+        num_paper,
+        if num_paper != 1 { "papers" } else { "paper" },
+    )))
+}
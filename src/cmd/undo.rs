@@ -0,0 +1,15 @@
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/undo.md");
+
+pub fn execute(
+    _input: CommandInput,
+    state: &mut State,
+    _config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let command = state
+        .undo
+        .undo(&mut state.papers, &mut state.trash.entries)?;
+
+    Ok(CommandOutput::Message(format!("Undid '{}'.\n", command)))
+}
@@ -12,7 +12,7 @@ pub fn execute(
         Some(list) => list,
         // Papers are specified as filter.
         None => {
-            match crate::cmd::ls::execute(input, state, config)? {
+            match crate::cmd::ls::filter(input, state, config)? {
                 CommandOutput::Papers(paper_list) => paper_list,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!(),
@@ -0,0 +1,228 @@
+use std::collections::BTreeSet;
+
+use rustyline::completion::Pair;
+
+use crate::cmd::{COMMANDS, MAN_SUBJECTS};
+use crate::filter::KEYWORDS;
+use crate::state::State;
+
+/// Tab completion candidates drawn from the paperbase.
+#[derive(Default)]
+pub struct PaperCompleter {
+    labels: BTreeSet<String>,
+    venues: BTreeSet<String>,
+    authors: BTreeSet<String>,
+    /// Keys of custom fields, followed by '='.
+    keys: BTreeSet<String>,
+}
+
+impl PaperCompleter {
+    /// Collect labels, venues, and authors from the current paperbase.
+    pub fn refresh(&mut self, state: &State) {
+        self.labels.clear();
+        self.venues.clear();
+        self.authors.clear();
+        self.keys.clear();
+        for paper in state.papers.iter() {
+            self.labels.extend(paper.labels.iter().cloned());
+            self.venues.insert(paper.venue.clone());
+            self.authors.extend(paper.authors.iter().cloned());
+            self.keys
+                .extend(paper.fields.keys().map(|key| format!("{}=", key)));
+        }
+    }
+
+    /// Returns the start position of the word under the cursor and
+    /// completion candidates for it.
+    /// - The first word of a command completes to command names.
+    /// - The word after `is` or `not` completes to labels, `at` to venues,
+    ///   `by` or `by1` to authors, and `where` to keys of custom fields.
+    ///   These match anywhere in the value.
+    ///   The word after `not` also completes to filter keywords.
+    /// - The argument of `man` completes to manual subjects.
+    /// - All other words complete to filter keywords.
+    pub fn complete(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let (words, start, partial) = split_words(&line[..pos]);
+
+        // Values are regexes in filters, but taken literally by `set` and `touch`.
+        let literal = matches!(
+            words.first().map(|w| w.as_str()),
+            Some("set") | Some("touch")
+        );
+        let values = |values: &BTreeSet<String>| substring_match(values, &partial, !literal);
+
+        let candidates = match words.as_slice() {
+            [] => prefix_match(COMMANDS.iter(), &partial),
+            [command] if command == "man" => {
+                prefix_match(COMMANDS.iter().chain(MAN_SUBJECTS.iter()), &partial)
+            }
+            [.., keyword] => match keyword.as_str() {
+                "is" => values(&self.labels),
+                // `not` either excludes a label or negates a keyword clause.
+                "not" => {
+                    let mut candidates = values(&self.labels);
+                    candidates.extend(prefix_match(KEYWORDS.iter(), &partial));
+                    candidates
+                }
+                "at" => values(&self.venues),
+                "by" | "by1" => values(&self.authors),
+                // Keys are not regexes.
+                "where" => substring_match(&self.keys, &partial, false),
+                _ => prefix_match(KEYWORDS.iter(), &partial),
+            },
+        };
+
+        (start, candidates)
+    }
+}
+
+/// Candidates that start with the partial word.
+fn prefix_match<'c>(candidates: impl Iterator<Item = &'c &'c str>, partial: &str) -> Vec<Pair> {
+    candidates
+        .filter(|c| c.starts_with(partial))
+        .map(|c| Pair {
+            display: c.to_string(),
+            replacement: c.to_string(),
+        })
+        .collect()
+}
+
+/// Candidates that contain the partial word, ignoring case.
+/// With `escape`, candidates are inserted as regexes that match them literally.
+fn substring_match(candidates: &BTreeSet<String>, partial: &str, escape: bool) -> Vec<Pair> {
+    let partial = partial.to_lowercase();
+    candidates
+        .iter()
+        .filter(|c| c.to_lowercase().contains(&partial))
+        .map(|c| Pair {
+            display: c.clone(),
+            replacement: if escape {
+                quote(&regex::escape(c))
+            } else {
+                quote(c)
+            },
+        })
+        .collect()
+}
+
+/// Split the last command in the line into complete words and the partial
+/// word under the cursor, following the rules of `parse_command`.
+/// Returns the complete words, the byte position where the partial word
+/// starts, and the partial word without its opening quote.
+fn split_words(line: &str) -> (Vec<String>, usize, String) {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    let mut inside_quotes = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && chars.peek().map(|&(_, c)| c) == Some('\'') {
+            chars.next();
+            current.push('\'');
+        } else if c == '\'' {
+            inside_quotes = !inside_quotes;
+        } else if !inside_quotes && (c.is_whitespace() || c == '|') {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            if c == '|' {
+                words.clear();
+            }
+            start = i + c.len_utf8();
+        } else {
+            current.push(c);
+        }
+    }
+    (words, start, current)
+}
+
+/// Quote a candidate if it would otherwise be split into multiple arguments.
+fn quote(candidate: &str) -> String {
+    if candidate.contains(|c: char| c.is_whitespace() || c == '|' || c == '\'') {
+        format!("'{}'", candidate.replace('\'', "\\'"))
+    } else {
+        candidate.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    fn completer() -> PaperCompleter {
+        let mut state = State::default();
+        state.papers.push(Paper {
+            title: "Shadowtutor".to_owned(),
+            authors: vec!["Jae-Won Chung".to_owned(), "Youngmoon Lee".to_owned()],
+            venue: "ICPP".to_owned(),
+            year: "2020".to_owned(),
+            labels: ["done".to_owned()].into(),
+            fields: [("code".to_owned(), "github.com".to_owned())].into(),
+            ..Default::default()
+        });
+        state.papers.push(Paper {
+            title: "Tensor Compilers".to_owned(),
+            authors: vec!["Tianqi Chen".to_owned()],
+            venue: "C++ Workshop (2021)".to_owned(),
+            year: "2021".to_owned(),
+            labels: ["c++".to_owned()].into(),
+            ..Default::default()
+        });
+        let mut completer = PaperCompleter::default();
+        completer.refresh(&state);
+        completer
+    }
+
+    fn replacements(line: &str) -> (usize, Vec<String>) {
+        let (start, pairs) = completer().complete(line, line.len());
+        (start, pairs.into_iter().map(|p| p.replacement).collect())
+    }
+
+    #[test]
+    fn command() {
+        assert_eq!(replacements("so"), (0, vec!["sort".to_owned()]));
+        assert_eq!(
+            replacements("ls | to"),
+            (5, vec!["top".to_owned(), "touch".to_owned()])
+        );
+    }
+
+    #[test]
+    fn keyword() {
+        assert_eq!(
+            replacements("ls foo b"),
+            (7, vec!["by".to_owned(), "by1".to_owned()])
+        );
+    }
+
+    #[test]
+    fn field() {
+        assert_eq!(
+            replacements("ls by chu"),
+            (6, vec![r"'Jae\-Won Chung'".to_owned()])
+        );
+        assert_eq!(
+            replacements("ls by 'jae-w"),
+            (6, vec![r"'Jae\-Won Chung'".to_owned()])
+        );
+        assert_eq!(replacements("ls at ic"), (6, vec!["ICPP".to_owned()]));
+        assert_eq!(replacements("ls | set is d"), (12, vec!["done".to_owned()]));
+        assert_eq!(replacements("ls where c"), (9, vec!["code=".to_owned()]));
+    }
+
+    #[test]
+    fn regex_escape() {
+        assert_eq!(
+            replacements("ls at c+"),
+            (6, vec![r"'C\+\+ Workshop \(2021\)'".to_owned()])
+        );
+        assert_eq!(replacements("ls is c+"), (6, vec![r"c\+\+".to_owned()]));
+        assert_eq!(replacements("ls | set is c+"), (12, vec!["c++".to_owned()]));
+    }
+
+    #[test]
+    fn man_subject() {
+        assert_eq!(replacements("man conf"), (4, vec!["config".to_owned()]));
+    }
+}
@@ -1,9 +1,11 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
 use crate::error::Fallacy;
+use crate::paper::{is_custom_key, SortKey};
 use crate::utils::{expand_tilde, expand_tilde_str};
 
 pub static MAN: &str = include_str!("../man/config.md");
@@ -13,25 +15,53 @@ pub struct Config {
     pub storage: StorageConfig,
     pub filter: FilterConfig,
     pub output: OutputConfig,
+    #[serde(default)]
+    pub sources: SourcesConfig,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct StorageConfig {
+    pub backend: Option<StorageBackend>,
     pub paper_metadata: PathBuf,
+    pub paper_database: Option<PathBuf>,
     pub command_history: PathBuf,
     pub max_history_size: usize,
     pub file_dir: PathBuf,
     pub note_dir: PathBuf,
+    pub backup_dir: Option<PathBuf>,
+    pub max_backups: Option<usize>,
+    pub max_undo: Option<usize>,
+    pub trash_dir: Option<PathBuf>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Where paper metadata is stored.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// A YAML file at `paper_metadata`.
+    Yaml,
+    /// An SQLite database at `paper_database`.
+    Sqlite,
+}
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct FilterConfig {
     pub case_insensitive_regex: bool,
 }
 
+/// Where `curl` and `lookup` fetch paper metadata from.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SourcesConfig {
+    pub crossref_url: Option<String>,
+    pub dblp_url: Option<String>,
+    pub openreview_url: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct OutputConfig {
     pub table_columns: Vec<String>,
+    pub sort: Option<Vec<String>>,
+    pub format: Option<OutputFormat>,
     pub viewer_command: Vec<String>,
     pub viewer_batch: bool,
     pub editor_command: Vec<String>,
@@ -41,6 +71,32 @@ pub struct OutputConfig {
     pub exclusive_label_groups: Option<Vec<HashSet<String>>>,
 }
 
+/// How paper lists are printed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Tsv,
+    Yaml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Fallacy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "yaml" => Ok(Self::Yaml),
+            _ => Err(Fallacy::UnknownOutputFormat(s.to_owned())),
+        }
+    }
+}
+
 impl Config {
     pub fn validate(&mut self) -> Result<(), Fallacy> {
         self.storage.validate()?;
@@ -53,13 +109,102 @@ impl Config {
 impl StorageConfig {
     fn validate(&mut self) -> Result<(), Fallacy> {
         self.paper_metadata = expand_tilde(&self.paper_metadata)?;
+        if let Some(paper_database) = &self.paper_database {
+            self.paper_database = Some(expand_tilde(paper_database)?);
+        }
         self.command_history = expand_tilde(&self.command_history)?;
         self.file_dir = expand_tilde(&self.file_dir)?;
         std::fs::create_dir_all(&self.file_dir)?;
         self.note_dir = expand_tilde(&self.note_dir)?;
         std::fs::create_dir_all(&self.note_dir)?;
+        if let Some(backup_dir) = &self.backup_dir {
+            self.backup_dir = Some(expand_tilde(backup_dir)?);
+        }
+        if let Some(trash_dir) = &self.trash_dir {
+            self.trash_dir = Some(expand_tilde(trash_dir)?);
+        }
         Ok(())
     }
+
+    /// Where paper metadata is stored. Defaults to YAML.
+    pub fn backend(&self) -> StorageBackend {
+        self.backend.unwrap_or(StorageBackend::Yaml)
+    }
+
+    /// The SQLite database used by the SQLite backend. Defaults to
+    /// 'metadata.sqlite3' next to the YAML metadata file.
+    pub fn paper_database(&self) -> PathBuf {
+        match &self.paper_database {
+            Some(path) => path.clone(),
+            None => self.paper_metadata.with_file_name("metadata.sqlite3"),
+        }
+    }
+
+    /// Where backups of paper metadata are kept. Defaults to the 'backups'
+    /// directory next to the metadata file.
+    pub fn backup_dir(&self) -> PathBuf {
+        match &self.backup_dir {
+            Some(dir) => dir.clone(),
+            None => self.paper_metadata.with_file_name("backups"),
+        }
+    }
+
+    /// Where removed papers and their files are kept. Defaults to the 'trash'
+    /// directory next to the metadata file.
+    pub fn trash_dir(&self) -> PathBuf {
+        match &self.trash_dir {
+            Some(dir) => dir.clone(),
+            None => self.paper_metadata.with_file_name("trash"),
+        }
+    }
+
+    /// Where `fsck` moves files and notes that no paper refers to. They are
+    /// not papers, so they are kept apart from the trash, in the 'orphans'
+    /// directory next to it.
+    pub fn orphan_dir(&self) -> PathBuf {
+        self.trash_dir().with_file_name("orphans")
+    }
+
+    /// How many backups of paper metadata are kept.
+    pub fn max_backups(&self) -> usize {
+        self.max_backups.unwrap_or(20)
+    }
+
+    /// How many commands can be undone.
+    pub fn max_undo(&self) -> usize {
+        self.max_undo.unwrap_or(20)
+    }
+
+    /// Where the undo history is stored, next to the command history.
+    pub fn undo_history(&self) -> PathBuf {
+        self.command_history.with_file_name("undo.yaml")
+    }
+}
+
+impl SourcesConfig {
+    /// The base URL of the Crossref REST API.
+    pub fn crossref_url(&self) -> &str {
+        self.crossref_url
+            .as_deref()
+            .unwrap_or("https://api.crossref.org")
+            .trim_end_matches('/')
+    }
+
+    /// The base URL of DBLP.
+    pub fn dblp_url(&self) -> &str {
+        self.dblp_url
+            .as_deref()
+            .unwrap_or("https://dblp.org")
+            .trim_end_matches('/')
+    }
+
+    /// The base URL of the OpenReview API.
+    pub fn openreview_url(&self) -> &str {
+        self.openreview_url
+            .as_deref()
+            .unwrap_or("https://api2.openreview.net")
+            .trim_end_matches('/')
+    }
 }
 
 impl FilterConfig {
@@ -70,13 +215,18 @@ impl FilterConfig {
 
 impl OutputConfig {
     fn validate(&mut self) -> Result<(), Fallacy> {
-        let allowed_columns = vec![
+        let allowed_columns = [
+            "id",
             "title",
             "authors",
             "first author",
             "venue",
             "year",
             "labels",
+            "added",
+            "modified",
+            "opened",
+            "read",
         ];
 
         // Convert everything to lowercase.
@@ -84,9 +234,9 @@ impl OutputConfig {
             *field = field.to_lowercase();
         }
 
-        // Check table columns.
+        // Check table columns. Custom fields can also be shown.
         for col in self.table_columns.iter() {
-            if !allowed_columns.contains(&&col[..]) {
+            if !allowed_columns.contains(&&col[..]) && !is_custom_key(col) {
                 return Err(Fallacy::ConfigAuditError(format!(
                     "Table column name {} is not supported.",
                     col
@@ -94,6 +244,16 @@ impl OutputConfig {
             }
         }
 
+        // Check default sort keys.
+        if let Some(sort) = &mut self.sort {
+            for key in sort.iter_mut() {
+                *key = key.to_lowercase();
+                if let Err(e) = SortKey::parse_list(key) {
+                    return Err(Fallacy::ConfigAuditError(e.to_string()));
+                }
+            }
+        }
+
         // Check viewer command and expand tilde.
         if self.viewer_command.is_empty() {
             return Err(Fallacy::ConfigAuditError(
@@ -145,6 +305,11 @@ impl Default for StorageConfig {
             path.push("metadata.yaml");
             path
         };
+        let paper_database = {
+            let mut path = data_dir.clone();
+            path.push("metadata.sqlite3");
+            path
+        };
         let command_history = {
             let mut path = data_dir.clone();
             path.push("history.txt");
@@ -160,32 +325,42 @@ impl Default for StorageConfig {
         };
 
         let note_dir = {
-            let mut path = data_dir;
+            let mut path = data_dir.clone();
             path.push("notes");
             path
         };
 
+        let backup_dir = {
+            let mut path = data_dir.clone();
+            path.push("backups");
+            path
+        };
+
+        let trash_dir = {
+            let mut path = data_dir;
+            path.push("trash");
+            path
+        };
+
         Self {
+            backend: Some(StorageBackend::Yaml),
             paper_metadata,
+            paper_database: Some(paper_database),
             command_history,
             max_history_size,
             file_dir: file_base_dir,
             note_dir,
-        }
-    }
-}
-
-impl Default for FilterConfig {
-    fn default() -> Self {
-        Self {
-            case_insensitive_regex: false,
+            backup_dir: Some(backup_dir),
+            max_backups: Some(20),
+            max_undo: Some(20),
+            trash_dir: Some(trash_dir),
         }
     }
 }
 
 impl Default for OutputConfig {
     fn default() -> Self {
-        let table_columns = vec!["title", "first author", "venue", "year", "labels"];
+        let table_columns = vec!["id", "title", "first author", "venue", "year", "labels"];
         let table_columns = table_columns.into_iter().map(|s| s.to_string()).collect();
         let viewer_command = vec![String::from("zathura")];
         let viewer_batch = false;
@@ -199,6 +374,8 @@ impl Default for OutputConfig {
 
         Self {
             table_columns,
+            sort: None,
+            format: None,
             viewer_command,
             viewer_batch,
             editor_command,
@@ -0,0 +1,310 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+
+use crate::bibtex::short_venue;
+use crate::error::Fallacy;
+use crate::paper::Paper;
+
+/// Characters escaped when a DOI is put in a URL path. DOIs may contain
+/// almost any character, e.g. '10.1002/(SICI)1097-4636(199706)35:4<443::AID-JBM5>3.0.CO;2-G'.
+const DOI_ESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Parse a DOI given as 'doi:10.1145/...', a doi.org URL, or bare.
+/// DOIs in URLs are percent-decoded.
+pub fn parse_doi(source: &str) -> Option<String> {
+    let source = source.trim();
+    let path = match url::Url::parse(source) {
+        Ok(url) if url.scheme().eq_ignore_ascii_case("doi") => url.path().to_owned(),
+        Ok(url) => {
+            let host = url.host_str()?;
+            if host != "doi.org" && !host.ends_with(".doi.org") {
+                return None;
+            }
+            url.path().trim_start_matches('/').to_owned()
+        }
+        Err(_) => return check_doi(source.to_owned()),
+    };
+    check_doi(percent_decode_str(&path).decode_utf8().ok()?.into_owned())
+}
+
+/// Return the DOI if it looks like one.
+fn check_doi(doi: String) -> Option<String> {
+    // DOIs are '10.{registrant}/{suffix}'.
+    let (prefix, suffix) = doi.split_once('/')?;
+    let registrant = prefix.strip_prefix("10.")?;
+    if registrant.is_empty()
+        || !registrant.chars().all(|c| c.is_ascii_digit() || c == '.')
+        || suffix.is_empty()
+    {
+        return None;
+    }
+    Some(doi)
+}
+
+/// Response of the Crossref `/works/{doi}` endpoint.
+#[derive(Deserialize)]
+struct Response {
+    message: Work,
+}
+
+/// Metadata of a work in Crossref. Only the fields we use are listed.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+struct Work {
+    #[serde(rename = "DOI")]
+    doi: String,
+    title: Vec<String>,
+    author: Vec<Author>,
+    container_title: Vec<String>,
+    short_container_title: Vec<String>,
+    event: Option<Event>,
+    issued: Option<Date>,
+    published: Option<Date>,
+}
+
+#[derive(Deserialize)]
+struct Author {
+    given: Option<String>,
+    family: Option<String>,
+    /// Organizations have a name instead.
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    acronym: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Date {
+    #[serde(rename = "date-parts")]
+    date_parts: Vec<Vec<Option<i64>>>,
+}
+
+impl Date {
+    fn year(&self) -> Option<i64> {
+        self.date_parts.first()?.first().copied().flatten()
+    }
+}
+
+impl Work {
+    /// A short venue, e.g. 'SOSP' for 'Proceedings of the ACM SIGOPS 28th
+    /// Symposium on Operating Systems Principles'.
+    fn venue(&self) -> String {
+        // Conference events have acronyms like "SOSP '21".
+        let acronym = self
+            .event
+            .as_ref()
+            .and_then(|e| e.acronym.as_deref())
+            .and_then(|a| a.split_whitespace().next())
+            .map(|a| a.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+            .filter(|a| !a.is_empty());
+        if let Some(acronym) = acronym {
+            return acronym.to_owned();
+        }
+        let container = self.container_title.first().map(|c| c.trim());
+        match container.map(short_venue) {
+            // Found an acronym in parentheses.
+            Some(short) if Some(short.as_str()) != container => short,
+            container => self
+                .short_container_title
+                .first()
+                .cloned()
+                .or(container)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn into_paper(self) -> Paper {
+        let mut paper = Paper {
+            title: self
+                .title
+                .first()
+                .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default(),
+            authors: self
+                .author
+                .iter()
+                .filter_map(|a| match (&a.given, &a.family, &a.name) {
+                    (Some(given), Some(family), _) => Some(format!("{} {}", given, family)),
+                    (None, Some(family), _) => Some(family.clone()),
+                    (_, _, name) => name.clone(),
+                })
+                .collect(),
+            venue: self.venue(),
+            year: self
+                .issued
+                .as_ref()
+                .and_then(Date::year)
+                .or_else(|| self.published.as_ref().and_then(Date::year))
+                .map(|y| y.to_string())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        paper.fields.insert("doi".to_owned(), self.doi);
+        paper
+    }
+}
+
+/// Fetch the metadata of the DOI from the Crossref REST API at `base_url`.
+pub fn fetch(
+    client: &reqwest::blocking::Client,
+    base_url: &str,
+    doi: &str,
+) -> Result<Paper, Fallacy> {
+    let res = client.get(works_url(base_url, doi)).send()?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Fallacy::CurlPaperNotFound("Crossref".to_owned()));
+    }
+    parse_work(&res.error_for_status()?.text()?)
+}
+
+/// The URL of the `/works/{doi}` endpoint for the DOI.
+fn works_url(base_url: &str, doi: &str) -> String {
+    format!(
+        "{}/works/{}",
+        base_url,
+        utf8_percent_encode(doi, DOI_ESCAPED)
+    )
+}
+
+/// Parse the response of the `/works/{doi}` endpoint.
+fn parse_work(json: &str) -> Result<Paper, Fallacy> {
+    let res: Response = serde_json::from_str(json)
+        .map_err(|e| Fallacy::CurlInvalidResponse("Crossref".to_owned(), e.to_string()))?;
+    let paper = res.message.into_paper();
+    if paper.title.is_empty() {
+        return Err(Fallacy::CurlCannotFindTitle(
+            "The work has no title.".to_owned(),
+        ));
+    }
+    if paper.authors.is_empty() {
+        return Err(Fallacy::CurlCannotFindAuthor(
+            "The work has no authors.".to_owned(),
+        ));
+    }
+    Ok(paper)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    static WORK: &str = r#"{
+  "status": "ok",
+  "message-type": "work",
+  "message": {
+    "DOI": "10.1145/3477132.3483552",
+    "type": "proceedings-article",
+    "title": ["Kangaroo: Caching Billions of Tiny Objects on Flash"],
+    "author": [
+      {"given": "Sara", "family": "McAllister", "sequence": "first"},
+      {"given": "Benjamin", "family": "Berg", "sequence": "additional"}
+    ],
+    "container-title": ["Proceedings of the ACM SIGOPS 28th Symposium on Operating Systems Principles"],
+    "event": {"name": "SOSP '21: ACM SIGOPS 28th Symposium on Operating Systems Principles", "acronym": "SOSP '21"},
+    "published": {"date-parts": [[2021, 10, 26]]},
+    "issued": {"date-parts": [[2021, 10, 26]]}
+  }
+}"#;
+
+    #[test]
+    fn dois() {
+        for (source, expected) in [
+            (
+                "doi:10.1145/3477132.3483552",
+                Some("10.1145/3477132.3483552"),
+            ),
+            ("https://doi.org/10.1109/5.771073", Some("10.1109/5.771073")),
+            (
+                "https://dx.doi.org/10.1145/3477132",
+                Some("10.1145/3477132"),
+            ),
+            ("10.1145/3477132", Some("10.1145/3477132")),
+            ("https://example.com/10.1145/3477132", None),
+            ("doi:11.1145/3477132", None),
+            ("10.1145", None),
+            ("2105.11367", None),
+            (
+                "https://doi.org/10.1002/(SICI)1097-4636(199706)35:4%3C443::AID-JBM5%3E3.0.CO;2-G",
+                Some("10.1002/(SICI)1097-4636(199706)35:4<443::AID-JBM5>3.0.CO;2-G"),
+            ),
+            ("https://doi.org/10.1000/a%23b%3Fc", Some("10.1000/a#b?c")),
+            ("10.1000/100%", Some("10.1000/100%")),
+        ] {
+            assert_eq!(parse_doi(source).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn escaped_doi() {
+        assert_eq!(
+            works_url("https://api.crossref.org", "10.1000/a#b?c%d<e>;f"),
+            "https://api.crossref.org/works/10.1000/a%23b%3Fc%25d%3Ce%3E%3Bf"
+        );
+    }
+
+    #[test]
+    fn venue() {
+        let work = |container: &str, short: &str| Work {
+            container_title: vec![container.to_owned()],
+            short_container_title: vec![short.to_owned()],
+            ..Default::default()
+        };
+        assert_eq!(
+            work(
+                "2020 IEEE Symposium on Security and Privacy (SP)",
+                "2020 IEEE SP"
+            )
+            .venue(),
+            "SP"
+        );
+        assert_eq!(
+            work("Communications of the ACM", "Commun. ACM").venue(),
+            "Commun. ACM"
+        );
+    }
+
+    #[test]
+    fn mock_server() {
+        // Serve one response, like the Crossref API would.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let len = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                WORK.len(),
+                WORK
+            )
+            .unwrap();
+            request
+        });
+
+        let client = reqwest::blocking::Client::new();
+        let paper = fetch(&client, &base_url, "10.1145/3477132.3483552").unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /works/10.1145/3477132.3483552 "));
+
+        assert_eq!(
+            paper.title,
+            "Kangaroo: Caching Billions of Tiny Objects on Flash"
+        );
+        assert_eq!(paper.authors, vec!["Sara McAllister", "Benjamin Berg"]);
+        assert_eq!(paper.venue, "SOSP");
+        assert_eq!(paper.year, "2021");
+        assert_eq!(paper.fields["doi"], "10.1145/3477132.3483552");
+    }
+}
@@ -0,0 +1,377 @@
+use roxmltree::Document;
+use serde::Deserialize;
+
+use crate::crossref::parse_doi;
+use crate::error::Fallacy;
+use crate::paper::Paper;
+
+/// Types of DBLP keys, i.e. the first part of 'conf/nsdi/YouWC21'.
+const KEY_TYPES: [&str; 7] = [
+    "conf",
+    "journals",
+    "books",
+    "series",
+    "reference",
+    "phd",
+    "tr",
+];
+
+/// A publication in DBLP.
+#[derive(Debug, PartialEq, Default)]
+pub struct DblpRecord {
+    /// The DBLP key, e.g. 'conf/nsdi/YouWC21'.
+    pub key: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    /// The short venue, e.g. 'NSDI'.
+    pub venue: String,
+    pub year: String,
+    pub doi: Option<String>,
+}
+
+/// Parse a DBLP key given as 'dblp:conf/nsdi/YouWC21', a record URL like
+/// 'https://dblp.org/rec/conf/nsdi/YouWC21.html', or bare.
+pub fn parse_key(source: &str) -> Option<String> {
+    let source = source.trim();
+    let key = match url::Url::parse(source) {
+        Ok(url) if url.scheme() == "dblp" => url.path().to_owned(),
+        Ok(url) => {
+            let host = url.host_str()?;
+            if !host.ends_with("dblp.org") && !host.ends_with("dblp.uni-trier.de") {
+                return None;
+            }
+            let key = url.path().strip_prefix("/rec/")?;
+            let key = key
+                .strip_suffix(".html")
+                .or_else(|| key.strip_suffix(".xml"))
+                .or_else(|| key.strip_suffix(".bib"))
+                .unwrap_or(key);
+            key.to_owned()
+        }
+        Err(_) => source.to_owned(),
+    };
+
+    let segments: Vec<_> = key.split('/').collect();
+    if segments.len() < 3
+        || !KEY_TYPES.contains(&segments[0])
+        || segments.iter().any(|s| s.is_empty())
+    {
+        return None;
+    }
+    Some(key)
+}
+
+/// Whether two titles are the same, ignoring case, punctuation, and spaces.
+pub fn same_title(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// DBLP tells apart people with the same name with a number, as in
+/// 'Wei Wang 0001'.
+fn clean_author(name: &str) -> String {
+    match name.trim().rsplit_once(' ') {
+        Some((name, number)) if number.len() == 4 && number.chars().all(|c| c.is_ascii_digit()) => {
+            name.to_owned()
+        }
+        _ => name.trim().to_owned(),
+    }
+}
+
+/// DBLP titles end with a period.
+fn clean_title(title: &str) -> String {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    title.strip_suffix('.').unwrap_or(&title).to_owned()
+}
+
+/// arXiv papers are in the 'CoRR' journal in DBLP.
+fn clean_venue(venue: &str) -> String {
+    match venue.trim() {
+        "CoRR" => "arXiv".to_owned(),
+        venue => venue.to_owned(),
+    }
+}
+
+impl DblpRecord {
+    /// Whether this is an informal publication, like an arXiv preprint.
+    pub fn is_informal(&self) -> bool {
+        self.key.starts_with("journals/corr/")
+    }
+
+    /// Turn into a paper. The DBLP key and the DOI are kept in custom fields.
+    pub fn into_paper(self) -> Paper {
+        let mut paper = Paper {
+            title: self.title,
+            authors: self.authors,
+            venue: self.venue,
+            year: self.year,
+            ..Default::default()
+        };
+        paper.fields.insert("dblp".to_owned(), self.key);
+        if let Some(doi) = self.doi {
+            paper.fields.insert("doi".to_owned(), doi);
+        }
+        paper
+    }
+}
+
+/// Fetch a record from DBLP at `base_url`.
+pub fn fetch(
+    client: &reqwest::blocking::Client,
+    base_url: &str,
+    key: &str,
+) -> Result<DblpRecord, Fallacy> {
+    let res = client.get(format!("{}/rec/{}.xml", base_url, key)).send()?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Fallacy::CurlPaperNotFound("DBLP".to_owned()));
+    }
+    parse_record(&res.error_for_status()?.text()?)
+}
+
+/// Search DBLP at `base_url` for publications with the title.
+pub fn search(
+    client: &reqwest::blocking::Client,
+    base_url: &str,
+    title: &str,
+) -> Result<Vec<DblpRecord>, Fallacy> {
+    let res = client
+        .get(format!("{}/search/publ/api", base_url))
+        .query(&[("q", title), ("format", "json"), ("h", "10")])
+        .send()?
+        .error_for_status()?;
+    parse_search(&res.text()?)
+}
+
+/// Parse the XML of a record, e.g. 'https://dblp.org/rec/conf/nsdi/YouWC21.xml'.
+fn parse_record(xml: &str) -> Result<DblpRecord, Fallacy> {
+    let invalid = |reason: String| Fallacy::CurlInvalidResponse("DBLP".to_owned(), reason);
+    let doc = Document::parse(xml).map_err(|e| invalid(e.to_string()))?;
+    let record = doc
+        .root_element()
+        .children()
+        .find(|n| n.is_element())
+        .ok_or_else(|| Fallacy::CurlPaperNotFound("DBLP".to_owned()))?;
+
+    let texts = |tag: &'static str| record.children().filter(move |n| n.has_tag_name(tag));
+    let text = |tag: &'static str| -> String {
+        texts(tag)
+            .next()
+            .map(|n| {
+                n.descendants()
+                    .filter(|d| d.is_text())
+                    .filter_map(|d| d.text())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let title = clean_title(&text("title"));
+    if title.is_empty() {
+        return Err(Fallacy::CurlCannotFindTitle(
+            "The record has no title.".to_owned(),
+        ));
+    }
+    let authors: Vec<String> = texts("author")
+        .filter_map(|n| n.text())
+        .map(clean_author)
+        .collect();
+    if authors.is_empty() {
+        return Err(Fallacy::CurlCannotFindAuthor(
+            "The record has no authors.".to_owned(),
+        ));
+    }
+    let venue = match text("booktitle") {
+        booktitle if !booktitle.is_empty() => booktitle,
+        _ => text("journal"),
+    };
+
+    Ok(DblpRecord {
+        key: record.attribute("key").unwrap_or_default().to_owned(),
+        title,
+        authors,
+        venue: clean_venue(&venue),
+        year: text("year"),
+        doi: texts("ee").filter_map(|n| n.text()).find_map(parse_doi),
+    })
+}
+
+/// Response of the DBLP publication search API.
+#[derive(Deserialize)]
+struct SearchResponse {
+    result: SearchResult,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    hits: Hits,
+}
+
+#[derive(Deserialize)]
+struct Hits {
+    /// Missing if nothing was found.
+    #[serde(default)]
+    hit: Vec<Hit>,
+}
+
+#[derive(Deserialize)]
+struct Hit {
+    info: Info,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    key: String,
+    title: String,
+    authors: Option<Authors>,
+    venue: Option<OneOrMany<String>>,
+    year: Option<String>,
+    doi: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authors {
+    author: OneOrMany<Author>,
+}
+
+#[derive(Deserialize)]
+struct Author {
+    text: String,
+}
+
+/// DBLP gives a single value instead of a list of one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(one) => vec![one],
+            Self::Many(many) => many,
+        }
+    }
+}
+
+/// Parse the JSON returned by the publication search API.
+fn parse_search(json: &str) -> Result<Vec<DblpRecord>, Fallacy> {
+    let res: SearchResponse = serde_json::from_str(json)
+        .map_err(|e| Fallacy::CurlInvalidResponse("DBLP".to_owned(), e.to_string()))?;
+    Ok(res
+        .result
+        .hits
+        .hit
+        .into_iter()
+        .map(|hit| {
+            let info = hit.info;
+            DblpRecord {
+                key: info.key,
+                title: clean_title(&info.title),
+                authors: info
+                    .authors
+                    .map(|a| a.author.into_vec())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|a| clean_author(&a.text))
+                    .collect(),
+                venue: info
+                    .venue
+                    .and_then(|v| v.into_vec().into_iter().next())
+                    .map(|v| clean_venue(&v))
+                    .unwrap_or_default(),
+                year: info.year.unwrap_or_default(),
+                doi: info.doi,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        for (source, expected) in [
+            ("conf/nsdi/YouWC21", Some("conf/nsdi/YouWC21")),
+            ("dblp:journals/cacm/DeanB13", Some("journals/cacm/DeanB13")),
+            (
+                "https://dblp.org/rec/conf/nsdi/YouWC21.html",
+                Some("conf/nsdi/YouWC21"),
+            ),
+            (
+                "https://dblp.uni-trier.de/rec/conf/osdi/GuCZ0S16.xml",
+                Some("conf/osdi/GuCZ0S16"),
+            ),
+            ("https://dblp.org/pid/12/3456.html", None),
+            ("cs/0112017", None),
+            ("conf/nsdi", None),
+        ] {
+            assert_eq!(parse_key(source).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn record() {
+        let xml = r#"<?xml version="1.0" encoding="US-ASCII"?>
+<dblp>
+<inproceedings key="conf/nsdi/YouWC21" mdate="2021-04-13">
+<author pid="274/1234">Jie You</author>
+<author pid="12/3456-1">Jingfeng Wu 0001</author>
+<author pid="68/1234">Mosharaf Chowdhury</author>
+<title>Ship Compute or Ship Data? Why Not Both?</title>
+<pages>633-651</pages>
+<year>2021</year>
+<booktitle>NSDI</booktitle>
+<ee type="oa">https://www.usenix.org/conference/nsdi21/presentation/you</ee>
+<crossref>conf/nsdi/2021</crossref>
+</inproceedings>
+</dblp>"#;
+        assert_eq!(
+            parse_record(xml).unwrap(),
+            DblpRecord {
+                key: "conf/nsdi/YouWC21".to_owned(),
+                title: "Ship Compute or Ship Data? Why Not Both?".to_owned(),
+                authors: vec![
+                    "Jie You".to_owned(),
+                    "Jingfeng Wu".to_owned(),
+                    "Mosharaf Chowdhury".to_owned()
+                ],
+                venue: "NSDI".to_owned(),
+                year: "2021".to_owned(),
+                doi: None,
+            }
+        );
+    }
+
+    #[test]
+    fn search_results() {
+        let json = r#"{"result":{"hits":{"@total":"2","hit":[
+{"@score":"9","info":{"authors":{"author":{"@pid":"1","text":"Jeffrey Dean"}},
+ "title":"The Tail at Scale.","venue":"Commun. ACM","year":"2013",
+ "type":"Journal Articles","key":"journals/cacm/DeanB13","doi":"10.1145/2408776.2408794"}},
+{"@score":"8","info":{"authors":{"author":[{"@pid":"2","text":"Jie You"},{"@pid":"3","text":"Wei Wang 0002"}]},
+ "title":"Ship Compute or Ship Data?","venue":"CoRR","year":"2020",
+ "type":"Informal Publications","key":"journals/corr/abs-2002-00001"}}]}}}"#;
+        let records = parse_search(json).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].title, "The Tail at Scale");
+        assert_eq!(records[0].authors, vec!["Jeffrey Dean"]);
+        assert_eq!(records[0].doi.as_deref(), Some("10.1145/2408776.2408794"));
+        assert!(!records[0].is_informal());
+        assert_eq!(records[1].authors, vec!["Jie You", "Wei Wang"]);
+        assert_eq!(records[1].venue, "arXiv");
+        assert!(records[1].is_informal());
+
+        let empty = r#"{"result":{"hits":{"@total":"0"}}}"#;
+        assert!(parse_search(empty).unwrap().is_empty());
+        assert!(same_title("The Tail at Scale", "the tail at scale."));
+    }
+}
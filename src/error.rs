@@ -13,8 +13,18 @@ pub enum Fallacy {
     StateDeserializeFailed(PathBuf, serde_yaml::Error),
     #[error("Failed to store paper metadata to '{0}': '{1}'")]
     StateStoreFailed(PathBuf, std::io::Error),
+    #[error("Paper metadata in '{0}' was written by a newer version of reason (layout version {1}). Please upgrade reason.")]
+    StateNewerVersion(PathBuf, u64),
+    #[error("Failed to upgrade paper metadata in '{0}': {1}")]
+    StateMigrationFailed(PathBuf, String),
     #[error("Failed to serialize and store paper metadata to '{0}': '{1}'")]
     StateSerializeFailed(PathBuf, serde_yaml::Error),
+    #[error("Paper metadata is being stored by another session. Lock file: '{0}'")]
+    StateLocked(PathBuf),
+    #[error("Failed to access the paper database at '{0}': '{1}'")]
+    StateDatabaseFailed(PathBuf, rusqlite::Error),
+    #[error("Failed to store undo history to '{0}': '{1}'")]
+    UndoStoreFailed(PathBuf, serde_yaml::Error),
     #[error("Failed to store command history to '{0}': '{1}'")]
     HistoryStoreFailed(PathBuf, std::io::Error),
     #[error("Failed to store command history to '{0}': '{1}'")]
@@ -34,12 +44,20 @@ pub enum Fallacy {
     IOError(#[from] std::io::Error),
     #[error("{0}")]
     FailedUserInteraction(String),
+    #[error("Unknown output format: '{0}'. Choose among table, json, csv, tsv, and yaml.")]
+    UnknownOutputFormat(String),
     // filter
     #[error("Failed to build filter from regex:\n{0}")]
     FilterBuildFailed(regex::Error),
+    #[error("Invalid filter: {0} Refer to `man filter`.")]
+    FilterInvalidSyntax(String),
     // paper
     #[error("Duplicate paper field keyword specified: '{0}'")]
     PaperDuplicateField(String),
+    #[error(
+        "'{0}' is a built-in paper field and cannot be set with '{0}=...'. Refer to `man paper`."
+    )]
+    PaperBuiltinField(String),
     #[error("Required paper fields not given: {0}")]
     PaperMissingFields(String),
     // path
@@ -52,6 +70,9 @@ pub enum Fallacy {
     // exit
     #[error("Exit reason")]
     ExitReason,
+    // ls command
+    #[error("`ls -f` expects an output format.")]
+    LsNoFormat,
     // man command
     #[error("`man` accepts exactly one argument.")]
     ManInvalidArgument,
@@ -60,6 +81,10 @@ pub enum Fallacy {
     // curl command
     #[error("`curl` accepts exactly one argument as source.")]
     CurlNoSource,
+    #[error("`curl` accepts exactly one source, but '{0}' was also given.")]
+    CurlExtraSource(String),
+    #[error("`{0}` is only supported for OpenReview sources.")]
+    CurlOpenReviewOnly(String),
     #[error("Invalid source: '{0}'. Refer to `man curl`.")]
     CurlInvalidSourceUrl(String),
     #[error("Failed to parse source url: '{0}'")]
@@ -72,10 +97,63 @@ pub enum Fallacy {
     CurlCannotFindAuthor(String),
     #[error("Failed to parse information from PDF File. {0}")]
     CurlPdfParsingError(#[from] PdfError),
+    #[error("Invalid response from {0}. {1}")]
+    CurlInvalidResponse(String, String),
+    #[error("{0} has no such paper.")]
+    CurlPaperNotFound(String),
+    // bib command
+    #[error("`bib -o` expects a path to write BibTeX entries to.")]
+    BibNoOutputPath,
+    #[error("`bib -i` expects a path to a BibTeX file to import.")]
+    BibNoInputPath,
     // printf command
     #[error("Failed to build book: '{0}'")]
     PrintfBuildError(#[from] mdbook::errors::Error),
+    // grep command
+    #[error("`grep` requires a regex to search notes with.")]
+    GrepNoPattern,
+    #[error("Failed to read note '{0}': '{1}'")]
+    GrepReadFailed(PathBuf, std::io::Error),
+    // sort command
+    #[error("No sort keys given and no default in `output.sort`.")]
+    SortNoKeys,
+    #[error("Unknown sort column: '{0}'. Refer to `man sort`.")]
+    SortInvalidKey(String),
+    // stat command
+    #[error("`stat -n` expects a number of lines.")]
+    StatInvalidLineCount,
+    // restore command
+    #[error("No backups found in '{0}'.")]
+    RestoreNoBackups(PathBuf),
+    #[error("Invalid backup: '{0}'. Run `restore` to list backups.")]
+    RestoreInvalidBackup(String),
+    // trash command
+    #[error("Failed to store the list of removed papers to '{0}': '{1}'")]
+    TrashStoreFailed(PathBuf, serde_yaml::Error),
+    #[error("Unknown subcommand: '{0}'. Refer to `man trash`.")]
+    TrashUnknownSubcommand(String),
+    #[error("`trash restore` requires a filter to select papers with.")]
+    TrashNoFilter,
+    #[error("Invalid number of days: '{0}'")]
+    TrashInvalidDays(String),
+    // undo and redo commands
+    #[error("Nothing to undo.")]
+    UndoNothing,
+    #[error("Nothing to redo.")]
+    RedoNothing,
+    #[error("'{0}' was changed afterwards. Cannot undo or redo the command.")]
+    UndoConflict(String),
     // set command
     #[error("No papers given through pipe.")]
     SetNoPapers,
+    // fsck command
+    #[error("Unknown option: '{0}'. Refer to `man fsck`.")]
+    FsckUnknownOption(String),
+    // convert command
+    #[error("`convert` requires the backend to convert to: 'yaml' or 'sqlite'.")]
+    ConvertNoBackend,
+    #[error("Unknown backend: '{0}'. Choose between 'yaml' and 'sqlite'.")]
+    ConvertUnknownBackend(String),
+    #[error("Paper metadata is already stored with the {0} backend.")]
+    ConvertSameBackend(String),
 }
@@ -1,22 +1,322 @@
 use std::fmt;
 
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Timelike};
 use regex::{Regex, RegexBuilder};
 
 use crate::error::Fallacy;
-use crate::paper::Paper;
+use crate::paper::{Event, Paper};
 
 pub static MAN: &str = include_str!("../man/filter.md");
 
+/// Propositional keywords that precede a field regex.
+pub static KEYWORDS: &[&str] = &[
+    "as", "by", "by1", "at", "in", "is", "not", "or", "where", "added", "modified", "opened",
+    "read",
+];
+
+/// A queryable paper field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Nickname,
+    Author,
+    FirstAuthor,
+    Venue,
+    Year,
+    Label,
+}
+
+impl Field {
+    /// The field that the given keyword refers to.
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "as" => Some(Self::Nickname),
+            "by" => Some(Self::Author),
+            "by1" => Some(Self::FirstAuthor),
+            "at" => Some(Self::Venue),
+            "in" => Some(Self::Year),
+            "is" => Some(Self::Label),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Nickname => "nickname",
+            Self::Author => "author",
+            Self::FirstAuthor => "first_author",
+            Self::Venue => "venue",
+            Self::Year => "year",
+            Self::Label => "label",
+        }
+    }
+
+    /// Check if the regex matches the field of the paper. For fields with
+    /// multiple values (authors and labels), at least one value should match.
+    fn matches(&self, regex: &Regex, paper: &Paper) -> bool {
+        match self {
+            Self::Title => regex.is_match(&paper.title),
+            Self::Nickname => regex.is_match(paper.nickname.as_deref().unwrap_or("")),
+            Self::Author => paper.authors.iter().any(|a| regex.is_match(a)),
+            Self::FirstAuthor => paper.authors.first().is_some_and(|a| regex.is_match(a)),
+            Self::Venue => regex.is_match(&paper.venue),
+            Self::Year => regex.is_match(&paper.year),
+            Self::Label => paper.labels.iter().any(|l| regex.is_match(l)),
+        }
+    }
+}
+
+/// A numeric comparison on the year of papers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YearCmp {
+    /// Inclusive on both ends. Either end may be open.
+    Range(Option<i64>, Option<i64>),
+    Lt(i64),
+    Le(i64),
+    Gt(i64),
+    Ge(i64),
+}
+
+impl YearCmp {
+    /// Parses '2018..2021', '2018..', '..2021', '>=2019', '<2015', etc.
+    /// Years should have four digits so that regexes like '20..' are not
+    /// mistaken for ranges.
+    fn parse(spec: &str) -> Option<Self> {
+        let year = |s: &str| -> Option<i64> {
+            if s.len() == 4 && s.chars().all(|c| c.is_ascii_digit()) {
+                s.parse().ok()
+            } else {
+                None
+            }
+        };
+        let open_year = |s: &str| -> Option<Option<i64>> {
+            if s.is_empty() {
+                Some(None)
+            } else {
+                year(s).map(Some)
+            }
+        };
+        if let Some(rest) = spec.strip_prefix(">=") {
+            year(rest).map(Self::Ge)
+        } else if let Some(rest) = spec.strip_prefix("<=") {
+            year(rest).map(Self::Le)
+        } else if let Some(rest) = spec.strip_prefix('>') {
+            year(rest).map(Self::Gt)
+        } else if let Some(rest) = spec.strip_prefix('<') {
+            year(rest).map(Self::Lt)
+        } else if let Some((min, max)) = spec.split_once("..") {
+            match (open_year(min)?, open_year(max)?) {
+                (None, None) => None,
+                (min, max) => Some(Self::Range(min, max)),
+            }
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, year: i64) -> bool {
+        match *self {
+            Self::Range(min, max) => {
+                min.is_none_or(|min| min <= year) && max.is_none_or(|max| year <= max)
+            }
+            Self::Lt(bound) => year < bound,
+            Self::Le(bound) => year <= bound,
+            Self::Gt(bound) => year > bound,
+            Self::Ge(bound) => year >= bound,
+        }
+    }
+}
+
+impl fmt::Display for YearCmp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let year = |y: Option<i64>| y.map(|y| y.to_string()).unwrap_or_default();
+        match *self {
+            Self::Range(min, max) => write!(f, "year is in {}..{}", year(min), year(max)),
+            Self::Lt(bound) => write!(f, "year < {}", bound),
+            Self::Le(bound) => write!(f, "year <= {}", bound),
+            Self::Gt(bound) => write!(f, "year > {}", bound),
+            Self::Ge(bound) => write!(f, "year >= {}", bound),
+        }
+    }
+}
+
+/// A comparison on when something happened to papers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeCmp {
+    /// At or after the time.
+    Since(DateTime<Local>),
+    /// Before the time.
+    Before(DateTime<Local>),
+}
+
+impl TimeCmp {
+    /// Parses 'since' or 'before', followed by a date like '2026-09-01' or
+    /// a time ago like '7d', '2w', '6m', or '1y'. A month is 30 days and a
+    /// year is 365 days.
+    fn parse(op: &str, spec: &str) -> Result<Self, Fallacy> {
+        let time = if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+            Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest()
+        } else {
+            let (num, unit) = spec.split_at(spec.len().saturating_sub(1));
+            let days = match unit {
+                "d" => Some(1),
+                "w" => Some(7),
+                "m" => Some(30),
+                "y" => Some(365),
+                _ => None,
+            };
+            match (num.parse::<i64>(), days) {
+                (Ok(num), Some(days)) if num >= 0 => {
+                    Some(Local::now() - Duration::days(num * days))
+                }
+                _ => None,
+            }
+        };
+        match (op, time) {
+            ("since", Some(time)) => Ok(Self::Since(time)),
+            ("before", Some(time)) => Ok(Self::Before(time)),
+            _ => Err(Fallacy::FilterInvalidSyntax(format!(
+                "Invalid date '{}'. Use a date like '2026-09-01' or a time ago like '7d', '2w', '6m', or '1y'.",
+                spec
+            ))),
+        }
+    }
+
+    pub fn matches(&self, time: DateTime<Local>) -> bool {
+        match *self {
+            Self::Since(since) => time >= since,
+            Self::Before(before) => time < before,
+        }
+    }
+}
+
+impl fmt::Display for TimeCmp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (op, time) = match *self {
+            Self::Since(time) => ("since", time),
+            Self::Before(time) => ("before", time),
+        };
+        // Show the time of day only if it was not given as a date.
+        if time.num_seconds_from_midnight() == 0 {
+            write!(f, "{} {}", op, time.format("%Y-%m-%d"))
+        } else {
+            write!(f, "{} {}", op, time.format("%Y-%m-%d %H:%M"))
+        }
+    }
+}
+
+/// Parse the year of a paper as a number.
+pub fn parse_year(year: &str) -> Option<i64> {
+    year.trim().parse().ok()
+}
+
+/// Parse a paper ID written as '#12'.
+fn parse_id(arg: &str) -> Option<u64> {
+    arg.strip_prefix('#')?.parse().ok()
+}
+
+/// A boolean expression over paper fields.
+#[derive(Debug, Clone)]
+pub enum FilterExpr {
+    /// The regex matches the field.
+    Match(Field, Regex),
+    /// The year of the paper satisfies the comparison. Papers whose
+    /// year is not a number never match, even when negated.
+    Year(YearCmp),
+    /// The paper has the ID.
+    Id(u64),
+    /// The paper has the custom field, and the regex, if any, matches its value.
+    Where(String, Option<Regex>),
+    /// The event happened to the paper in the time range. Papers to which the
+    /// event never happened do not match.
+    Time(Event, TimeCmp),
+    Not(Box<FilterExpr>),
+    And(Vec<FilterExpr>),
+    Or(Vec<FilterExpr>),
+}
+
+impl FilterExpr {
+    /// Evaluate the expression in three-valued logic. Comparing a year that
+    /// is not a number is undecided (`None`), and so is its negation, so that
+    /// such papers never match a comparison.
+    fn eval(&self, paper: &Paper) -> Option<bool> {
+        match self {
+            Self::Match(field, regex) => Some(field.matches(regex, paper)),
+            Self::Year(cmp) => parse_year(&paper.year).map(|year| cmp.matches(year)),
+            Self::Id(id) => Some(paper.id == *id),
+            Self::Where(key, regex) => Some(
+                paper
+                    .fields
+                    .get(key)
+                    .is_some_and(|value| regex.as_ref().is_none_or(|r| r.is_match(value))),
+            ),
+            Self::Time(event, cmp) => {
+                Some(paper.time(*event).is_some_and(|time| cmp.matches(time)))
+            }
+            Self::Not(expr) => expr.eval(paper).map(|matches| !matches),
+            Self::And(exprs) => all(exprs.iter().map(|e| e.eval(paper))),
+            Self::Or(exprs) => any(exprs.iter().map(|e| e.eval(paper))),
+        }
+    }
+}
+
+/// Three-valued conjunction: false if any is false, otherwise undecided if
+/// any is undecided.
+fn all(values: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    let mut result = Some(true);
+    for value in values {
+        match value {
+            Some(false) => return Some(false),
+            None => result = None,
+            Some(true) => {}
+        }
+    }
+    result
+}
+
+/// Three-valued disjunction: true if any is true, otherwise undecided if
+/// any is undecided.
+fn any(values: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    all(values.map(|value| value.map(|v| !v))).map(|v| !v)
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Match(field, regex) => write!(f, "{} matches '{}'", field.name(), regex),
+            Self::Year(cmp) => write!(f, "{}", cmp),
+            Self::Id(id) => write!(f, "id is #{}", id),
+            Self::Where(key, Some(regex)) => write!(f, "{} matches '{}'", key, regex),
+            Self::Where(key, None) => write!(f, "{} is set", key),
+            Self::Time(event, cmp) => write!(f, "{} {}", event.name(), cmp),
+            Self::Not(expr) => match expr.as_ref() {
+                Self::Match(field, regex) => {
+                    write!(f, "{} does not match '{}'", field.name(), regex)
+                }
+                Self::Where(key, Some(regex)) => {
+                    write!(f, "{} does not match '{}'", key, regex)
+                }
+                Self::Where(key, None) => write!(f, "{} is not set", key),
+                // `And` and `Or` are already parenthesized.
+                expr => write!(f, "not {}", expr),
+            },
+            Self::And(exprs) => {
+                let joined: Vec<_> = exprs.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", joined.join(" and "))
+            }
+            Self::Or(exprs) => {
+                let joined: Vec<_> = exprs.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", joined.join(" or "))
+            }
+        }
+    }
+}
+
+/// A paper filter. Papers should match all terms in the filter.
 #[derive(Default, Debug, Clone)]
 pub struct PaperFilter {
-    pub title: Vec<Regex>,
-    pub nickname: Vec<Regex>,
-    pub author: Vec<Regex>,
-    pub first_author: Vec<Regex>,
-    pub venue: Vec<Regex>,
-    pub year: Vec<Regex>,
-    pub is_label: Vec<Regex>,
-    pub not_label: Vec<Regex>,
+    pub terms: Vec<FilterExpr>,
 }
 
 impl PaperFilter {
@@ -24,160 +324,257 @@ impl PaperFilter {
     /// instance of `PaperFilter`. Remove the command (first argument)
     /// and pass the rest to this function.
     pub fn from_args(args: &[String], case_insensitive: bool) -> Result<Self, Fallacy> {
-        let mut filter = Self::default();
-        let mut arg_iter = args.iter();
-        while let Some(arg) = arg_iter.next() {
-            let (mut place, item) = match arg.as_ref() {
-                "as" => (&mut filter.nickname, arg_iter.next()),
-                "by" => (&mut filter.author, arg_iter.next()),
-                "by1" => (&mut filter.first_author, arg_iter.next()),
-                "at" => (&mut filter.venue, arg_iter.next()),
-                "in" => (&mut filter.year, arg_iter.next()),
-                "is" => (&mut filter.is_label, arg_iter.next()),
-                "not" => (&mut filter.not_label, arg_iter.next()),
-                _ => (&mut filter.title, Some(arg)),
-            };
-            let item = match item {
-                Some(string) => string,
-                None => {
-                    // If no matching regex is found, instead match title.
-                    place = &mut filter.title;
-                    arg
-                }
-            };
-            match RegexBuilder::new(item)
-                .case_insensitive(case_insensitive)
-                .build()
-            {
-                Ok(regex) => place.push(regex),
-                Err(e) => return Err(Fallacy::FilterBuildFailed(e)),
-            }
+        let mut parser = Parser {
+            args,
+            pos: 0,
+            case_insensitive,
+        };
+        if args.is_empty() {
+            return Ok(Self::default());
+        }
+        let expr = parser.parse_or()?;
+        if let Some(arg) = parser.peek() {
+            // `parse_or` only stops early at a closing parenthesis.
+            return Err(Fallacy::FilterInvalidSyntax(format!(
+                "Unmatched '{}'.",
+                arg
+            )));
         }
-        Ok(filter)
+        let terms = match expr {
+            FilterExpr::And(exprs) => exprs,
+            expr => vec![expr],
+        };
+        Ok(Self { terms })
     }
 
     /// Merges multiple filters into one.
     pub fn merge(filters: &[Self]) -> Self {
         let mut merged = Self::default();
         for filter in filters {
-            merged.title.extend(filter.title.clone());
-            merged.nickname.extend(filter.nickname.clone());
-            merged.author.extend(filter.author.clone());
-            merged.first_author.extend(filter.first_author.clone());
-            merged.venue.extend(filter.venue.clone());
-            merged.year.extend(filter.year.clone());
-            merged.is_label.extend(filter.is_label.clone());
-            merged.not_label.extend(filter.not_label.clone());
+            merged.terms.extend(filter.terms.iter().cloned());
         }
         merged
     }
 
     /// Check if the filter matches the given paper.
     pub fn matches(&self, paper: &Paper) -> bool {
-        macro_rules! checker {
-            // A field value should match all regexes in the filter.
-            ($regex_field:ident) => {
-                if !self
-                    .$regex_field
-                    .iter()
-                    .all(|regex| regex.is_match(paper.$regex_field.as_ref()))
-                {
-                    return false;
+        all(self.terms.iter().map(|term| term.eval(paper))) == Some(true)
+    }
+
+    /// Check if this filter is empty.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Check if the filter cannot decide whether the paper matches, because
+    /// the year of the paper is not a number and the filter compares years.
+    pub fn undecided(&self, paper: &Paper) -> bool {
+        all(self.terms.iter().map(|term| term.eval(paper))).is_none()
+    }
+}
+
+/// A recursive descent parser for filter arguments.
+///
+/// expr  := and ('or' and)*
+/// and   := unary+
+/// unary := '(' expr ')' | 'not' unary | 'not' label | keyword value
+///        | event ('since' | 'before') date | title
+///
+/// The value of `in` is a numeric year comparison if it looks like one, and
+/// a regex otherwise.
+///
+/// `not` negates the following clause if it is a keyword or a parenthesis,
+/// and otherwise excludes the label that follows.
+struct Parser<'a> {
+    args: &'a [String],
+    pos: usize,
+    case_insensitive: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.pos).map(|s| s.as_str())
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.peek();
+        self.pos += 1;
+        arg
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, Fallacy> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek() == Some("or") {
+            self.next();
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            FilterExpr::Or(exprs)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, Fallacy> {
+        let mut exprs = Vec::new();
+        while let Some(arg) = self.peek() {
+            if arg == "or" || arg == ")" {
+                break;
+            }
+            exprs.push(self.parse_unary()?);
+        }
+        match exprs.len() {
+            0 => Err(Fallacy::FilterInvalidSyntax(match self.peek() {
+                Some(arg) => format!("Expected a filter before '{}'.", arg),
+                None => "Expected a filter at the end.".to_owned(),
+            })),
+            1 => Ok(exprs.remove(0)),
+            _ => Ok(FilterExpr::And(exprs)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr, Fallacy> {
+        // `parse_and` only calls this when there is a next argument.
+        let arg = self.next().unwrap();
+        // 'added since 2026-09-01' and the like.
+        if self.time_filter_at(self.pos - 1) {
+            let op = self.next().unwrap();
+            let spec = self.next().unwrap();
+            let event = Event::from_name(arg).unwrap();
+            return Ok(FilterExpr::Time(event, TimeCmp::parse(op, spec)?));
+        }
+        match arg {
+            "(" => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(")") => Ok(expr),
+                    _ => Err(Fallacy::FilterInvalidSyntax("Unmatched '('.".to_owned())),
                 }
-            };
-            // Same as above, but with a custom field getter.
-            ($regex_field:ident, getter => $field_getter:expr) => {
-                if !self
-                    .$regex_field
-                    .iter()
-                    .all(|regex| regex.is_match($field_getter))
+            }
+            "not" => match self.peek() {
+                Some(next)
+                    if next == "("
+                        || next == "not"
+                        || next == "where"
+                        || self.time_filter_at(self.pos)
+                        || Field::from_keyword(next).is_some()
+                        || parse_id(next).is_some() =>
                 {
-                    return false;
+                    Ok(FilterExpr::Not(Box::new(self.parse_unary()?)))
                 }
-            };
-            // At least one element in the vector field should match all regexes in the filter.
-            ($regex_field:ident, vector => $vec_field:ident) => {
-                if !self
-                    .$regex_field
-                    .iter()
-                    .all(|regex| paper.$vec_field.iter().any(|field| regex.is_match(field)))
-                {
-                    return false;
+                Some(label) => {
+                    self.next();
+                    Ok(FilterExpr::Not(Box::new(self.regex(Field::Label, label)?)))
                 }
-            };
-            // None of the elements in the vector field should match any regex in the filter.
-            ($regex_field:ident, vector =!> $vec_field:ident) => {
-                if !self
-                    .$regex_field
-                    .iter()
-                    .all(|regex| paper.$vec_field.iter().all(|field| !regex.is_match(field)))
-                {
-                    return false;
+                // If no matching regex is found, instead match title.
+                None => self.regex(Field::Title, arg),
+            },
+            // '#12' selects the paper with ID 12.
+            _ if parse_id(arg).is_some() => Ok(FilterExpr::Id(parse_id(arg).unwrap())),
+            // 'where key=regex' matches a custom field, and 'where key'
+            // matches papers that have it.
+            "where" if self.peek().is_some() => {
+                let spec = self.next().unwrap();
+                match spec.split_once('=') {
+                    Some((key, regex)) => Ok(FilterExpr::Where(
+                        key.to_lowercase(),
+                        Some(self.build(regex)?),
+                    )),
+                    None => Ok(FilterExpr::Where(spec.to_lowercase(), None)),
                 }
-            };
+            }
+            _ => match (Field::from_keyword(arg), self.peek()) {
+                (Some(field), Some(value)) => {
+                    self.next();
+                    match YearCmp::parse(value) {
+                        Some(cmp) if field == Field::Year => Ok(FilterExpr::Year(cmp)),
+                        _ => self.regex(field, value),
+                    }
+                }
+                // If no matching regex is found, instead match title.
+                _ => self.regex(Field::Title, arg),
+            },
         }
-
-        checker!(title);
-        checker!(nickname, getter => paper.nickname.as_ref().unwrap_or(&"".to_string()));
-        checker!(author, vector => authors);
-        checker!(first_author, getter => paper.authors.first().unwrap());
-        checker!(venue);
-        checker!(year);
-        checker!(is_label, vector => labels);
-        checker!(not_label, vector =!> labels);
-
-        true
     }
 
-    /// Check if this filter is empty.
-    pub fn is_empty(&self) -> bool {
-        macro_rules! checker {
-            ($field:ident) => {
-                if !self.$field.is_empty() {
-                    return false;
-                }
-            };
+    /// Check if a time filter starts at the position: an event, 'since' or
+    /// 'before', and a date. Otherwise, event names are regexes.
+    fn time_filter_at(&self, pos: usize) -> bool {
+        match self.args.get(pos..pos + 3) {
+            Some([event, op, _]) => {
+                Event::from_name(event).is_some() && (op == "since" || op == "before")
+            }
+            _ => false,
         }
+    }
 
-        checker!(title);
-        checker!(nickname);
-        checker!(author);
-        checker!(first_author);
-        checker!(venue);
-        checker!(year);
-        checker!(is_label);
-        checker!(not_label);
+    fn regex(&self, field: Field, item: &str) -> Result<FilterExpr, Fallacy> {
+        Ok(FilterExpr::Match(field, self.build(item)?))
+    }
 
-        true
+    fn build(&self, item: &str) -> Result<Regex, Fallacy> {
+        match RegexBuilder::new(item)
+            .case_insensitive(self.case_insensitive)
+            .build()
+        {
+            Ok(regex) => Ok(regex),
+            Err(e) => Err(Fallacy::FilterBuildFailed(e)),
+        }
     }
 }
 
 impl fmt::Display for PaperFilter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Simple terms on the same field are grouped together, in a fixed
+        // order of fields. All other terms come after, in the order given.
+        let order = [
+            (Field::Title, true),
+            (Field::Nickname, true),
+            (Field::Author, true),
+            (Field::FirstAuthor, true),
+            (Field::Venue, true),
+            (Field::Year, true),
+            (Field::Label, true),
+            (Field::Label, false),
+        ];
+        let mut grouped: Vec<Vec<String>> = vec![Vec::new(); order.len()];
+        let mut others = Vec::new();
+        for term in self.terms.iter() {
+            let simple = match term {
+                FilterExpr::Match(field, regex) => Some((*field, true, regex)),
+                FilterExpr::Not(expr) => match expr.as_ref() {
+                    FilterExpr::Match(field, regex) => Some((*field, false, regex)),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match simple.and_then(|(field, matches, regex)| {
+                order
+                    .iter()
+                    .position(|&o| o == (field, matches))
+                    .map(|i| (i, regex))
+            }) {
+                Some((i, regex)) => grouped[i].push(regex.to_string()),
+                None => others.push(term.to_string()),
+            }
+        }
+
         let mut segments = Vec::new();
-        let displayer = |ret: &mut Vec<String>, filter: &Vec<Regex>, name: &str, matches: bool| {
-            let joined = filter
-                .iter()
-                .map(|re| re.to_string())
-                .reduce(|a, b| format!("{}' & '{}", a, b));
-            if let Some(joined) = joined {
-                ret.push(format!(
+        for ((field, matches), regexes) in order.iter().zip(grouped) {
+            if !regexes.is_empty() {
+                segments.push(format!(
                     "{} {} '{}'",
-                    name,
-                    if matches { "matches" } else { "does not match" },
-                    joined
+                    field.name(),
+                    if *matches {
+                        "matches"
+                    } else {
+                        "does not match"
+                    },
+                    regexes.join("' & '")
                 ));
             }
-        };
-
-        displayer(&mut segments, &self.title, "title", true);
-        displayer(&mut segments, &self.nickname, "nickname", true);
-        displayer(&mut segments, &self.author, "author", true);
-        displayer(&mut segments, &self.first_author, "first_author", true);
-        displayer(&mut segments, &self.venue, "venue", true);
-        displayer(&mut segments, &self.year, "year", true);
-        displayer(&mut segments, &self.is_label, "label", true);
-        displayer(&mut segments, &self.not_label, "label", false);
+        }
+        segments.extend(others);
 
         if segments.is_empty() {
             writeln!(f, "No filters are active.")
@@ -186,3 +583,124 @@ impl fmt::Display for PaperFilter {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn paper() -> Paper {
+        Paper {
+            id: 7,
+            title: "Shadowtutor: Distributed Partial Distillation".to_owned(),
+            authors: vec!["Jae-Won Chung".to_owned(), "Jae-Yun Kim".to_owned()],
+            venue: "ICPP".to_owned(),
+            year: "2020".to_owned(),
+            labels: ["done".to_owned()].into(),
+            fields: [("dataset".to_owned(), "ImageNet".to_owned())].into(),
+            added: Some("2020-03-01T12:00:00+09:00".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    macro_rules! filter_test {
+        ($name:ident: $args:expr, $display:expr, $matches:expr) => {
+            #[test]
+            fn $name() {
+                let args: Vec<String> = $args.split_whitespace().map(String::from).collect();
+                let filter = PaperFilter::from_args(&args, false).unwrap();
+                assert_eq!(filter.to_string().trim_end(), $display);
+                assert_eq!(filter.matches(&paper()), $matches);
+            }
+        };
+    }
+
+    filter_test!(empty: "", "No filters are active.", true);
+    filter_test!(grouped: "Deep by Chung by Jeong",
+        "title matches 'Deep', author matches 'Chung' & 'Jeong'", false);
+    filter_test!(label: "is done not active",
+        "label matches 'done', label does not match 'active'", true);
+    filter_test!(keyword_as_title: "Shadow by",
+        "title matches 'Shadow' & 'by'", false);
+    filter_test!(or: "at OSDI or at ICPP",
+        "(venue matches 'OSDI' or venue matches 'ICPP')", true);
+    filter_test!(or_and: "at OSDI by Chung or in 2020",
+        "((venue matches 'OSDI' and author matches 'Chung') or year matches '2020')", true);
+    filter_test!(not_field: "not by Chung",
+        "author does not match 'Chung'", false);
+    filter_test!(not_group: "Shadow not ( at OSDI or at SOSP )",
+        "title matches 'Shadow', not (venue matches 'OSDI' or venue matches 'SOSP')", true);
+    filter_test!(group: "( at OSDI or at ICPP ) ( by Kim or by Lee )",
+        "(venue matches 'OSDI' or venue matches 'ICPP'), (author matches 'Kim' or author matches 'Lee')", true);
+
+    filter_test!(year_range: "in 2018..2021",
+        "year is in 2018..2021", true);
+    filter_test!(year_open_range: "in ..2019",
+        "year is in ..2019", false);
+    filter_test!(year_ge: "in >=2020 in <2021",
+        "year >= 2020, year < 2021", true);
+    filter_test!(year_regex: "in 20..",
+        "year matches '20..'", true);
+    filter_test!(not_year: "not in >2019",
+        "not year > 2019", false);
+
+    #[test]
+    fn unparsed_year() {
+        let paper = Paper {
+            year: "n.d.".to_owned(),
+            venue: "ICPP".to_owned(),
+            ..Default::default()
+        };
+        for (args, matches, undecided) in [
+            ("in >=2015", false, true),
+            ("not in >=2015", false, true),
+            ("not ( in >=2015 at OSDI )", true, false),
+            ("at ICPP or in >=2015", true, false),
+            ("at OSDI or in >=2015", false, true),
+            ("at OSDI in >=2015", false, false),
+            ("in 20..", false, false),
+        ] {
+            let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+            let filter = PaperFilter::from_args(&args, false).unwrap();
+            assert_eq!(filter.matches(&paper), matches, "{:?}", args);
+            assert_eq!(filter.undecided(&paper), undecided, "{:?}", args);
+        }
+    }
+    filter_test!(id: "#7",
+        "id is #7", true);
+    filter_test!(id_or: "#3 or #8",
+        "(id is #3 or id is #8)", false);
+    filter_test!(not_id: "not #7",
+        "not id is #7", false);
+    filter_test!(id_title: "#tag",
+        "title matches '#tag'", false);
+    filter_test!(field: "where Dataset=^Image",
+        "dataset matches '^Image'", true);
+    filter_test!(field_set: "where code or where dataset",
+        "(code is set or dataset is set)", true);
+    filter_test!(not_field_set: "not where dataset",
+        "dataset is not set", false);
+    filter_test!(added_since: "added since 2020-01-01",
+        "added since 2020-01-01", true);
+    filter_test!(added_before: "added before 2020-01-01",
+        "added before 2020-01-01", false);
+    filter_test!(not_added_recently: "not added since 2020-06-01",
+        "not added since 2020-06-01", true);
+    filter_test!(never_opened: "opened since 2000-01-01",
+        "opened since 2000-01-01", false);
+
+    #[test]
+    fn invalid_syntax() {
+        for args in [
+            "( at OSDI",
+            "at OSDI )",
+            "or at OSDI",
+            "at OSDI or",
+            "( )",
+            "added since yesterday",
+            "read before 3x",
+        ] {
+            let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+            assert!(PaperFilter::from_args(&args, false).is_err());
+        }
+    }
+}
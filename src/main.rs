@@ -1,10 +1,18 @@
 mod app;
+mod arxiv;
+mod bibtex;
 mod cmd;
+mod completion;
 mod config;
+mod crossref;
+mod dblp;
 mod error;
 mod filter;
+mod openreview;
 mod paper;
 mod state;
+#[cfg(test)]
+mod testing;
 mod utils;
 
 use crate::app::App;
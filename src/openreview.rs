@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::Fallacy;
+use crate::paper::Paper;
+
+/// Review fields that come first in notes, in this order.
+const REVIEW_FIELDS: [&str; 7] = [
+    "rating",
+    "confidence",
+    "summary",
+    "strengths",
+    "weaknesses",
+    "questions",
+    "review",
+];
+
+/// Parse the forum ID from an OpenReview URL, e.g.
+/// 'https://openreview.net/forum?id=YicbFdNTTy'.
+pub fn parse_forum_id(source: &str) -> Option<String> {
+    let url = url::Url::parse(source.trim()).ok()?;
+    if !url.host_str()?.ends_with("openreview.net") {
+        return None;
+    }
+    url.query_pairs()
+        .find(|(key, _)| key == "id")
+        .map(|(_, id)| id.into_owned())
+        .filter(|id| !id.is_empty())
+}
+
+/// Response of the `/notes` endpoint.
+#[derive(Deserialize)]
+struct Notes {
+    notes: Vec<Note>,
+}
+
+/// A note in an OpenReview forum, e.g. a submission or a review.
+#[derive(Deserialize)]
+struct Note {
+    id: String,
+    /// API v2 notes have a list of invitations.
+    #[serde(default)]
+    invitations: Vec<String>,
+    /// API v1 notes have one invitation.
+    invitation: Option<String>,
+    #[serde(default)]
+    signatures: Vec<String>,
+    cdate: Option<i64>,
+    pdate: Option<i64>,
+    #[serde(default)]
+    content: BTreeMap<String, Value>,
+}
+
+impl Note {
+    /// A field of the content. API v2 wraps values in `{"value": ...}`.
+    fn field(&self, key: &str) -> Option<&Value> {
+        let value = self.content.get(key)?;
+        match value.get("value") {
+            Some(inner) => Some(inner),
+            None if value.is_object() => None,
+            None => Some(value),
+        }
+    }
+
+    fn string(&self, key: &str) -> Option<String> {
+        self.field(key)
+            .and_then(Value::as_str)
+            .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|s| !s.is_empty())
+    }
+
+    fn strings(&self, key: &str) -> Vec<String> {
+        match self.field(key) {
+            Some(Value::Array(values)) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            // Some venues ask for comma-separated keywords.
+            Some(Value::String(values)) => values
+                .split(',')
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn invitations(&self) -> impl Iterator<Item = &String> {
+        self.invitations.iter().chain(self.invitation.iter())
+    }
+
+    fn is_review(&self) -> bool {
+        self.invitations().any(|i| i.ends_with("/Official_Review"))
+    }
+
+    /// The venue and year from an ID like 'ICLR.cc/2023/Conference', which
+    /// is the venue ID of accepted papers and the prefix of invitations.
+    fn venue_and_year(&self) -> (String, String) {
+        let ids = self
+            .string("venueid")
+            .into_iter()
+            .chain(self.invitations().cloned());
+        for id in ids {
+            let mut parts = id.split('/');
+            let venue = parts.next().unwrap_or_default();
+            let venue = venue.strip_suffix(".cc").unwrap_or(venue);
+            let year = parts.next().unwrap_or_default();
+            if !venue.is_empty() && year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()) {
+                return (venue.to_owned(), year.to_owned());
+            }
+        }
+
+        // Fall back to a venue like 'ICLR 2023 poster' and the date of the note.
+        let venue = self
+            .string("venue")
+            .and_then(|v| v.split_whitespace().next().map(|v| v.to_owned()))
+            .unwrap_or_default();
+        let year = self
+            .pdate
+            .or(self.cdate)
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+            .map(|date| date.year().to_string())
+            .unwrap_or_default();
+        (venue, year)
+    }
+
+    /// The reviewer, e.g. 'Reviewer abcd' for
+    /// 'ICLR.cc/2023/Conference/Submission123/Reviewer_abcd'.
+    fn signature(&self) -> String {
+        self.signatures
+            .first()
+            .and_then(|s| s.rsplit('/').next())
+            .map(|s| s.replace('_', " "))
+            .unwrap_or_else(|| "Anonymous".to_owned())
+    }
+
+    /// The review in markdown.
+    fn review(&self) -> String {
+        let mut review = format!("### {}\n\n", self.signature());
+        let rest = self
+            .content
+            .keys()
+            .map(|k| k.as_str())
+            .filter(|k| !REVIEW_FIELDS.contains(k));
+        for key in REVIEW_FIELDS.iter().copied().chain(rest) {
+            let value = match self.field(key) {
+                Some(Value::String(s)) => s.trim().to_owned(),
+                Some(Value::Null) | None => continue,
+                Some(value) => value.to_string(),
+            };
+            if value.is_empty() {
+                continue;
+            }
+            let name = key.replace('_', " ");
+            let mut chars = name.chars();
+            let name: String = match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => continue,
+            };
+            review.push_str(&format!("**{}**: {}\n\n", name, value));
+        }
+        review
+    }
+}
+
+/// An OpenReview submission with its reviews.
+#[derive(Debug)]
+pub struct Submission {
+    /// The paper without its file.
+    pub paper: Paper,
+    pub keywords: Vec<String>,
+    /// Official reviews in markdown, if any.
+    pub reviews: Option<String>,
+}
+
+/// Where PDF files of submissions are, given the forum ID as `id`. This is
+/// the API host without its 'api' or 'api2' prefix, e.g.
+/// 'https://openreview.net/pdf' for 'https://api2.openreview.net'.
+pub fn pdf_url(base_url: &str) -> String {
+    for prefix in ["://api2.", "://api."].iter() {
+        if base_url.contains(prefix) {
+            return format!("{}/pdf", base_url.replacen(prefix, "://", 1));
+        }
+    }
+    format!("{}/pdf", base_url)
+}
+
+/// The API v1 counterpart of an API v2 base URL, e.g.
+/// 'https://api.openreview.net' for 'https://api2.openreview.net'.
+fn v1_url(base_url: &str) -> Option<String> {
+    if base_url.contains("://api2.") {
+        Some(base_url.replacen("://api2.", "://api.", 1))
+    } else {
+        None
+    }
+}
+
+/// Fetch the submission of the forum from the OpenReview API at `base_url`.
+/// Forums that API v2 does not know, which are mostly older ones, are
+/// fetched from API v1.
+pub fn fetch(
+    client: &reqwest::blocking::Client,
+    base_url: &str,
+    forum: &str,
+) -> Result<Submission, Fallacy> {
+    match fetch_from(client, base_url, forum) {
+        Err(Fallacy::CurlPaperNotFound(source)) => match v1_url(base_url) {
+            Some(v1) => fetch_from(client, &v1, forum),
+            None => Err(Fallacy::CurlPaperNotFound(source)),
+        },
+        result => result,
+    }
+}
+
+fn fetch_from(
+    client: &reqwest::blocking::Client,
+    base_url: &str,
+    forum: &str,
+) -> Result<Submission, Fallacy> {
+    let res = client
+        .get(format!("{}/notes", base_url))
+        .query(&[("forum", forum)])
+        .send()?
+        .error_for_status()?;
+    parse_forum(&res.text()?, forum)
+}
+
+/// Parse the notes in a forum, which are the submission and its replies.
+fn parse_forum(json: &str, forum: &str) -> Result<Submission, Fallacy> {
+    let res: Notes = serde_json::from_str(json)
+        .map_err(|e| Fallacy::CurlInvalidResponse("OpenReview".to_owned(), e.to_string()))?;
+    let submission = res
+        .notes
+        .iter()
+        .find(|n| n.id == forum)
+        .ok_or_else(|| Fallacy::CurlPaperNotFound("OpenReview".to_owned()))?;
+
+    let title = submission
+        .string("title")
+        .ok_or_else(|| Fallacy::CurlCannotFindTitle("The note has no title.".to_owned()))?;
+    let authors = submission.strings("authors");
+    if authors.is_empty() {
+        return Err(Fallacy::CurlCannotFindAuthor(
+            "The note has no authors. Submissions under review are anonymous.".to_owned(),
+        ));
+    }
+    let (venue, year) = submission.venue_and_year();
+    let keywords = submission.strings("keywords");
+
+    let mut paper = Paper {
+        title,
+        authors,
+        venue,
+        year,
+        ..Default::default()
+    };
+    paper
+        .fields
+        .insert("openreview".to_owned(), forum.to_owned());
+    if let Some(abs) = submission.string("abstract") {
+        paper.fields.insert("abstract".to_owned(), abs);
+    }
+    if !keywords.is_empty() {
+        paper
+            .fields
+            .insert("keywords".to_owned(), keywords.join(", "));
+    }
+
+    let reviews: Vec<_> = res
+        .notes
+        .iter()
+        .filter(|n| n.is_review())
+        .map(Note::review)
+        .collect();
+    Ok(Submission {
+        paper,
+        keywords,
+        reviews: if reviews.is_empty() {
+            None
+        } else {
+            Some(format!("## Reviews\n\n{}", reviews.join("")))
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn forum_ids() {
+        for (source, expected) in [
+            (
+                "https://openreview.net/forum?id=YicbFdNTTy",
+                Some("YicbFdNTTy"),
+            ),
+            (
+                "https://openreview.net/pdf?id=YicbFdNTTy&noteId=abc",
+                Some("YicbFdNTTy"),
+            ),
+            ("https://openreview.net/forum", None),
+            ("https://example.com/forum?id=YicbFdNTTy", None),
+        ] {
+            assert_eq!(parse_forum_id(source).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn forum_v2() {
+        let json = r#"{"notes": [
+  {"id": "R1", "forum": "F1",
+   "invitations": ["ICLR.cc/2023/Conference/Submission9/-/Official_Review"],
+   "signatures": ["ICLR.cc/2023/Conference/Submission9/Reviewer_abcd"],
+   "content": {"summary_of_the_paper": {"value": "It scales."}, "rating": {"value": 8}, "summary": {"value": " Good. "}}},
+  {"id": "F1", "forum": "F1",
+   "invitations": ["ICLR.cc/2023/Conference/-/Submission"],
+   "cdate": 1663000000000,
+   "content": {
+     "title": {"value": "An Image is Worth 16x16 Words"},
+     "authors": {"value": ["Alexey Dosovitskiy", "Lucas Beyer"]},
+     "keywords": {"value": ["Vision Transformer", "image recognition"]},
+     "abstract": {"value": "We show that\n a pure transformer works."},
+     "venue": {"value": "ICLR 2021 Oral"},
+     "venueid": {"value": "ICLR.cc/2021/Conference"}}}
+]}"#;
+        let submission = parse_forum(json, "F1").unwrap();
+        let paper = &submission.paper;
+        assert_eq!(paper.title, "An Image is Worth 16x16 Words");
+        assert_eq!(paper.authors, vec!["Alexey Dosovitskiy", "Lucas Beyer"]);
+        assert_eq!(paper.venue, "ICLR");
+        assert_eq!(paper.year, "2021");
+        assert_eq!(paper.fields["openreview"], "F1");
+        assert_eq!(
+            paper.fields["abstract"],
+            "We show that a pure transformer works."
+        );
+        assert_eq!(
+            submission.keywords,
+            vec!["Vision Transformer", "image recognition"]
+        );
+        assert_eq!(
+            submission.reviews.unwrap(),
+            "## Reviews\n\n### Reviewer abcd\n\n**Rating**: 8\n\n**Summary**: Good.\n\n**Summary of the paper**: It scales.\n\n"
+        );
+    }
+
+    #[test]
+    fn forum_v1() {
+        let json = r#"{"notes": [
+  {"id": "F2", "forum": "F2",
+   "invitation": "NeurIPS.cc/2019/Conference/-/Blind_Submission",
+   "content": {"title": "Deep Leakage from Gradients", "authors": ["Ligeng Zhu"], "keywords": "privacy, federated learning"}}
+]}"#;
+        let submission = parse_forum(json, "F2").unwrap();
+        assert_eq!(submission.paper.venue, "NeurIPS");
+        assert_eq!(submission.paper.year, "2019");
+        assert_eq!(submission.keywords, vec!["privacy", "federated learning"]);
+        assert!(submission.reviews.is_none());
+        assert!(matches!(
+            parse_forum(json, "F3"),
+            Err(Fallacy::CurlPaperNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn urls() {
+        let v2 = "https://api2.openreview.net";
+        assert_eq!(pdf_url(v2), "https://openreview.net/pdf");
+        assert_eq!(v1_url(v2).as_deref(), Some("https://api.openreview.net"));
+        assert_eq!(
+            pdf_url("https://api.openreview.net"),
+            "https://openreview.net/pdf"
+        );
+        assert_eq!(
+            pdf_url("http://localhost:8080"),
+            "http://localhost:8080/pdf"
+        );
+        assert_eq!(v1_url("http://localhost:8080"), None);
+    }
+}
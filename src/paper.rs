@@ -1,22 +1,314 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::io::Write;
 use std::path::PathBuf;
 
+use chrono::{DateTime, Local, SecondsFormat};
 use comfy_table::{Attribute, Cell, CellAlignment, Color, ContentArrangement, Table};
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
+use crate::config::{Config, OutputFormat};
 use crate::error::Fallacy;
 use crate::state::State;
 use crate::utils::{as_filename, make_unique_path};
 
 pub static MAN: &str = include_str!("../man/paper.md");
 
-pub struct PaperList(pub Vec<usize>);
+/// Names of built-in paper fields, which custom fields cannot use.
+pub static BUILTIN_FIELDS: &[&str] = &[
+    "id", "title", "nickname", "authors", "venue", "year", "filepath", "labels", "notepath",
+    "added", "modified", "opened", "read",
+];
+
+/// Things that happen to a paper, whose time is recorded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Added by `touch`, `curl`, or `bib -i`.
+    Added,
+    /// Changed by `set`.
+    Modified,
+    /// Opened by `open`.
+    Opened,
+    /// Note opened by `ed`.
+    Read,
+}
+
+impl Event {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "added" => Some(Self::Added),
+            "modified" => Some(Self::Modified),
+            "opened" => Some(Self::Opened),
+            "read" => Some(Self::Read),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Modified => "modified",
+            Self::Opened => "opened",
+            Self::Read => "read",
+        }
+    }
+}
+
+/// Check if the string can be the key of a custom field. Keys start with a
+/// letter and consist of letters, digits, '_', and '-'.
+pub fn is_custom_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !BUILTIN_FIELDS.contains(&key)
+}
+
+/// Split an argument like 'dataset=ImageNet' into the lowercase key and the
+/// value of a custom field. Returns `None` if the argument is not one.
+pub fn parse_custom_field(arg: &str) -> Result<Option<(String, &str)>, Fallacy> {
+    let (key, value) = match arg.split_once('=') {
+        Some((key, value)) => (key.to_lowercase(), value),
+        None => return Ok(None),
+    };
+    if BUILTIN_FIELDS.contains(&key.as_str()) {
+        return Err(Fallacy::PaperBuiltinField(key));
+    }
+    if !is_custom_key(&key) {
+        return Ok(None);
+    }
+    Ok(Some((key, value)))
+}
+
+/// A list of papers, referred to by their IDs.
+pub struct PaperList(pub Vec<u64>);
+
+/// A paper field to sort paper lists with, along with the sort direction.
+#[derive(Debug, PartialEq)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+}
+
+impl SortKey {
+    /// Parses a comma-separated list of sort keys.
+    /// Each key is a column name optionally followed by 'asc' or 'desc'.
+    /// For instance, "year desc, first author".
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>, Fallacy> {
+        let sortable = [
+            "id",
+            "title",
+            "nickname",
+            "authors",
+            "first author",
+            "venue",
+            "year",
+            "labels",
+            "added",
+            "modified",
+            "opened",
+            "read",
+        ];
+
+        let mut keys = Vec::new();
+        for key in spec.split(',') {
+            let key = key.trim().to_lowercase();
+            if key.is_empty() {
+                continue;
+            }
+            let (column, descending) = if let Some(column) = key.strip_suffix(" desc") {
+                (column.trim(), true)
+            } else if let Some(column) = key.strip_suffix(" asc") {
+                (column.trim(), false)
+            } else {
+                (key.as_str(), false)
+            };
+            if !sortable.contains(&column) {
+                return Err(Fallacy::SortInvalidKey(column.to_owned()));
+            }
+            keys.push(Self {
+                column: column.to_owned(),
+                descending,
+            });
+        }
+        Ok(keys)
+    }
+
+    /// Compare two papers with this key.
+    /// Years are compared numerically if possible, times chronologically,
+    /// and all other fields case-insensitively.
+    fn compare(&self, a: &Paper, b: &Paper) -> Ordering {
+        let ordering = if self.column == "id" {
+            a.id.cmp(&b.id)
+        } else if self.column == "year" {
+            match (a.year.trim().parse::<i64>(), b.year.trim().parse::<i64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                // Years that don't parse come after all other years,
+                // in both directions.
+                (Ok(_), Err(_)) => return Ordering::Less,
+                (Err(_), Ok(_)) => return Ordering::Greater,
+                (Err(_), Err(_)) => a.year.cmp(&b.year),
+            }
+        } else if let Some(event) = Event::from_name(&self.column) {
+            // Papers without the time come first.
+            a.time(event).cmp(&b.time(event))
+        } else {
+            let a = a.field_as_string(&self.column).to_lowercase();
+            let b = b.field_as_string(&self.column).to_lowercase();
+            a.cmp(&b)
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// A flat view of a paper used for machine-readable output.
+/// File and note paths are absolute.
+#[derive(Serialize)]
+struct PaperRecord<'p> {
+    id: u64,
+    title: &'p str,
+    nickname: Option<&'p str>,
+    authors: &'p [String],
+    venue: &'p str,
+    year: &'p str,
+    labels: Vec<&'p str>,
+    filepath: Option<PathBuf>,
+    notepath: Option<PathBuf>,
+    added: Option<&'p str>,
+    modified: Option<&'p str>,
+    opened: Option<&'p str>,
+    read: Option<&'p str>,
+    fields: &'p BTreeMap<String, String>,
+}
+
+impl<'p> PaperRecord<'p> {
+    fn new(paper: &'p Paper, config: &Config) -> Self {
+        let mut labels: Vec<_> = paper.labels.iter().map(|s| s.as_str()).collect();
+        labels.sort_unstable();
+        Self {
+            id: paper.id,
+            title: &paper.title,
+            nickname: paper.nickname.as_deref(),
+            authors: &paper.authors,
+            venue: &paper.venue,
+            year: &paper.year,
+            labels,
+            filepath: paper.filepath(config),
+            notepath: paper
+                .notepath
+                .as_ref()
+                .map(|notepath| config.storage.note_dir.join(notepath)),
+            added: paper.added.as_deref(),
+            modified: paper.modified.as_deref(),
+            opened: paper.opened.as_deref(),
+            read: paper.read.as_deref(),
+            fields: &paper.fields,
+        }
+    }
+
+    /// Header row for delimiter-separated output.
+    fn header() -> Vec<&'static str> {
+        vec![
+            "id", "title", "nickname", "authors", "venue", "year", "labels", "filepath",
+            "notepath", "added", "modified", "opened", "read", "fields",
+        ]
+    }
+
+    /// Values in the same order as `header`. Lists are joined with commas.
+    fn values(&self) -> Vec<String> {
+        let path = |p: &Option<PathBuf>| {
+            p.as_ref()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        vec![
+            self.id.to_string(),
+            self.title.to_owned(),
+            self.nickname.unwrap_or_default().to_owned(),
+            self.authors.join(", "),
+            self.venue.to_owned(),
+            self.year.to_owned(),
+            self.labels.join(","),
+            path(&self.filepath),
+            path(&self.notepath),
+            self.added.unwrap_or_default().to_owned(),
+            self.modified.unwrap_or_default().to_owned(),
+            self.opened.unwrap_or_default().to_owned(),
+            self.read.unwrap_or_default().to_owned(),
+            self.fields
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]
+    }
+}
 
 impl PaperList {
-    pub fn into_string(self, state: &State, config: &Config) -> String {
+    /// Sort the paper list with the given keys. Earlier keys take precedence,
+    /// and papers that compare equal with all keys keep their original order.
+    /// IDs of papers that no longer exist come last.
+    pub fn sort(&mut self, state: &State, keys: &[SortKey]) {
+        self.0
+            .sort_by(|&a, &b| match (state.paper(a), state.paper(b)) {
+                (Some(a), Some(b)) => keys
+                    .iter()
+                    .map(|key| key.compare(a, b))
+                    .find(|&ordering| ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+    }
+
+    /// Format the paper list in the given format. Papers that no longer exist
+    /// are skipped.
+    pub fn into_string(self, state: &State, config: &Config, format: OutputFormat) -> String {
+        let records = || {
+            self.0
+                .iter()
+                .filter_map(|&id| state.paper(id))
+                .map(|paper| PaperRecord::new(paper, config))
+        };
+        match format {
+            OutputFormat::Table => self.into_table(state, config),
+            OutputFormat::Json => records()
+                .map(|r| serde_json::to_string(&r).unwrap_or_default() + "\n")
+                .collect(),
+            OutputFormat::Yaml => {
+                serde_yaml::to_string(&records().collect::<Vec<_>>()).unwrap_or_default()
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let delimiter = if format == OutputFormat::Csv {
+                    ','
+                } else {
+                    '\t'
+                };
+                let mut rows = vec![PaperRecord::header()
+                    .into_iter()
+                    .map(|s| s.to_owned())
+                    .collect()];
+                rows.extend(records().map(|r| r.values()));
+                rows.into_iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|v| delimited(v, delimiter))
+                            .collect::<Vec<_>>()
+                            .join(&delimiter.to_string())
+                            + "\n"
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn into_table(self, state: &State, config: &Config) -> String {
         let mut table = Table::new();
 
         // Content width is dynamically arranged.
@@ -31,19 +323,23 @@ impl PaperList {
         table.set_header(header);
 
         // One row per paper.
-        for ind in self.0 {
-            let p = &state.papers[ind];
+        for id in self.0 {
+            let p = match state.paper(id) {
+                Some(p) => p,
+                None => continue,
+            };
             let mut row = Vec::new();
             for col in config.output.table_columns.iter() {
                 row.push(p.field_as_string(col));
             }
 
-            let labels: &Vec<String> = &state.papers[ind].labels.clone().into_iter().collect();
+            let mut labels: Vec<_> = p.labels.iter().collect();
+            labels.sort_unstable();
             let mut is_painted = false;
 
             if let Some(colors) = &config.output.label_colors {
                 for label in labels.iter().rev() {
-                    if let Some(color) = colors.get(label) {
+                    if let Some(color) = colors.get(*label) {
                         table
                             .add_row(row.iter().map(|s| {
                                 Cell::new(s).fg(Color::try_from(color.as_str()).unwrap())
@@ -63,8 +359,14 @@ impl PaperList {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct Paper {
+    /// A unique number assigned to the paper when it is added to the paperbase.
+    /// IDs do not change when other papers are removed. This field is queryable.
+    /// Keyword: None. An argument like '#12' selects the paper with ID 12.
+    #[serde(default)]
+    pub id: u64,
+
     /// The title of the paper, in full. This field is queryable.
     /// Keyword: None. An argument without a keyword is considered a title.
     pub title: String,
@@ -98,6 +400,25 @@ pub struct Paper {
     /// title of the paper. If collisions are detected, an integer will be appended
     /// to the file name.
     pub notepath: Option<PathBuf>,
+
+    /// When the paper was added, modified, opened, and when its note was last
+    /// opened, in RFC 3339. Recorded automatically. These fields are queryable.
+    /// Keyword: 'added', 'modified', 'opened', 'read', followed by 'since' or
+    /// 'before' and a date, as in 'added since 2026-09-01'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opened: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read: Option<String>,
+
+    /// Custom fields, like the URL of the code or the dataset used, keyed by
+    /// lowercase names. This field is queryable.
+    /// Keyword: 'where', as in 'where dataset=ImageNet'
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
 }
 
 impl Paper {
@@ -173,6 +494,7 @@ impl Paper {
         let notepath = None;
 
         Ok(Paper {
+            id: 0,
             title,
             nickname,
             authors,
@@ -181,14 +503,25 @@ impl Paper {
             labels,
             filepath,
             notepath,
+            added: None,
+            modified: None,
+            opened: None,
+            read: None,
+            fields: BTreeMap::new(),
         })
     }
 
     pub fn apply_from_args(&mut self, config: &Config, args: &[String]) -> Result<(), Fallacy> {
         // Collect a mapping of keyword -> Option<argument>.
         let mut map = HashMap::new();
+        let mut fields = Vec::new();
         let mut arg_iter = args.iter().cloned();
         while let Some(arg) = arg_iter.next() {
+            // Custom fields look like 'key=value'.
+            if let Some((key, value)) = parse_custom_field(&arg)? {
+                fields.push((key, value.to_owned()));
+                continue;
+            }
             match arg.as_ref() {
                 "as" | "by" | "at" | "in" | "is" | "not" => {
                     if map.contains_key(arg.as_str()) {
@@ -248,7 +581,15 @@ impl Paper {
         }
         if let Some(labels) = map.remove("not") {
             for label in labels.split(',') {
-                self.labels.remove(&label.trim().to_string());
+                self.labels.remove(label.trim());
+            }
+        }
+        // An empty value removes the field.
+        for (key, value) in fields {
+            if value.is_empty() {
+                self.fields.remove(&key);
+            } else {
+                self.fields.insert(key, value);
             }
         }
 
@@ -257,19 +598,60 @@ impl Paper {
 
     pub fn field_as_string(&self, field: &str) -> String {
         match field {
+            "id" => self.id.to_string(),
             "title" => self.title.clone(),
             "nickname" => self.nickname.clone().unwrap_or_default(),
             "authors" => self.authors.join(", "),
-            "first author" => self.authors.first().unwrap().clone(),
+            "first author" => self.authors.first().cloned().unwrap_or_default(),
             "venue" => self.venue.clone(),
             "year" => self.year.clone(),
-            "labels" => self
-                .labels
-                .clone()
-                .into_iter()
-                .collect::<Vec<_>>()
-                .join(","),
-            _ => "".to_string(),
+            // Labels are unordered, so sort them for stable output and sorting.
+            "labels" => {
+                let mut labels: Vec<_> = self.labels.iter().map(|s| s.as_str()).collect();
+                labels.sort_unstable();
+                labels.join(",")
+            }
+            field => match Event::from_name(field) {
+                Some(event) => self
+                    .time(event)
+                    .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+                None => self.fields.get(field).cloned().unwrap_or_default(),
+            },
+        }
+    }
+
+    /// When the event last happened to the paper.
+    pub fn time(&self, event: Event) -> Option<DateTime<Local>> {
+        let time = match event {
+            Event::Added => &self.added,
+            Event::Modified => &self.modified,
+            Event::Opened => &self.opened,
+            Event::Read => &self.read,
+        };
+        time.as_deref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| time.with_timezone(&Local))
+    }
+
+    /// Record that the event happened to the paper just now.
+    pub fn record(&mut self, event: Event) {
+        let now = Some(Local::now().to_rfc3339_opts(SecondsFormat::Secs, false));
+        match event {
+            Event::Added => self.added = now,
+            Event::Modified => self.modified = now,
+            Event::Opened => self.opened = now,
+            Event::Read => self.read = now,
+        }
+    }
+
+    /// The paper without the times it was opened or read, which change
+    /// whenever the paper is accessed rather than edited.
+    pub fn without_access(&self) -> Self {
+        Self {
+            opened: None,
+            read: None,
+            ..self.clone()
         }
     }
 
@@ -279,6 +661,7 @@ impl Paper {
     /// a new note file is created and filled with some default content.
     pub fn notepath(&mut self, config: &Config, create: bool) -> Result<Option<PathBuf>, Fallacy> {
         let note;
+        let missing = self.notepath.is_some();
         // Paper has note path.
         if let Some(notepath) = self.notepath.as_ref() {
             note = {
@@ -315,6 +698,13 @@ impl Paper {
         if !create {
             Ok(None)
         } else {
+            // Don't replace a missing note without telling the user.
+            if missing {
+                eprintln!(
+                    "The note of '{}' was missing. Created a new one at {:?}.",
+                    self.title, note
+                );
+            }
             // Create/truncate the note file and fill with default content.
             match std::fs::File::create(&note) {
                 Ok(mut file) => {
@@ -345,3 +735,115 @@ impl Paper {
         })
     }
 }
+
+/// Escape a value for delimiter-separated output.
+/// CSV values are quoted as in RFC 4180, and TSV values have tabs and
+/// newlines replaced with spaces.
+fn delimited(value: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        value.replace(['\t', '\n', '\r'], " ")
+    } else if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    macro_rules! sort_key_test {
+        ($name:ident: $spec:expr, $answer:expr) => {
+            #[test]
+            fn $name() {
+                let answer: Vec<(&str, bool)> = $answer;
+                let answer: Vec<SortKey> = answer
+                    .into_iter()
+                    .map(|(column, descending)| SortKey {
+                        column: column.to_owned(),
+                        descending,
+                    })
+                    .collect();
+                assert_eq!(SortKey::parse_list($spec).unwrap(), answer);
+            }
+        };
+    }
+
+    sort_key_test!(single: "year", vec![("year", false)]);
+    sort_key_test!(direction: "year desc", vec![("year", true)]);
+    sort_key_test!(many: "year desc, first author", vec![("year", true), ("first author", false)]);
+    sort_key_test!(case_and_space: " Venue  ASC ,TITLE desc ", vec![("venue", false), ("title", true)]);
+
+    #[test]
+    fn unparsed_years_last() {
+        let paper = |year: &str| Paper {
+            year: year.to_owned(),
+            ..Default::default()
+        };
+        let mut papers = [paper("n.d."), paper("2019"), paper("2021")];
+        for (spec, answer) in [
+            ("year", ["2019", "2021", "n.d."]),
+            ("year desc", ["2021", "2019", "n.d."]),
+        ] {
+            let keys = SortKey::parse_list(spec).unwrap();
+            papers.sort_by(|a, b| keys[0].compare(a, b));
+            let years: Vec<_> = papers.iter().map(|p| p.year.as_str()).collect();
+            assert_eq!(years, answer);
+        }
+    }
+
+    #[test]
+    fn sort_by_labels() {
+        use crate::testing::paper;
+        let mut papers = [
+            paper(1, "").labeled(&["ml", "done", "active"]),
+            paper(2, "").labeled(&["done", "active"]),
+        ];
+        assert_eq!(papers[0].field_as_string("labels"), "active,done,ml");
+        let keys = SortKey::parse_list("labels desc").unwrap();
+        papers.sort_by(|a, b| keys[0].compare(a, b));
+        assert_eq!(papers[0].id, 1);
+    }
+
+    #[test]
+    fn invalid_column() {
+        assert!(SortKey::parse_list("year, publisher").is_err());
+    }
+
+    #[test]
+    fn custom_fields() {
+        let config = Config::default();
+        let mut paper = Paper::default();
+        let args: Vec<String> = ["Dataset=ImageNet", "code=https://x.org/?a=b"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        paper.apply_from_args(&config, &args).unwrap();
+        assert_eq!(paper.field_as_string("dataset"), "ImageNet");
+        assert_eq!(paper.field_as_string("code"), "https://x.org/?a=b");
+        assert_eq!(paper.title, "");
+
+        paper
+            .apply_from_args(&config, &["dataset=".to_owned()])
+            .unwrap();
+        assert!(!paper.fields.contains_key("dataset"));
+        assert!(paper
+            .apply_from_args(&config, &["year=2020".to_owned()])
+            .is_err());
+
+        // Not a custom field, so this is the title.
+        paper
+            .apply_from_args(&config, &["E = mc^2".to_owned()])
+            .unwrap();
+        assert_eq!(paper.title, "E = mc^2");
+    }
+
+    #[test]
+    fn record_columns() {
+        let config = Config::default();
+        let paper = Paper::default();
+        let record = PaperRecord::new(&paper, &config);
+        assert_eq!(PaperRecord::header().len(), record.values().len());
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime};
+
+use crate::config::StorageConfig;
+use crate::error::Fallacy;
+
+/// Prefix of backup file names. The rest of the name is the time of the backup,
+/// preceded by `v{version}-` for backups taken before upgrading the layout.
+static PREFIX: &str = "metadata-";
+
+/// Format of the time in backup file names.
+static TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// Copy the metadata file into the backup directory, and remove the oldest
+/// backups so that at most `max_backups` remain.
+pub fn create(metadata: &Path, config: &StorageConfig) -> Result<(), Fallacy> {
+    save(&std::fs::read(metadata)?, config)
+}
+
+/// Write serialized paper metadata into the backup directory, and remove the
+/// oldest backups so that at most `max_backups` remain.
+pub fn save(content: &[u8], config: &StorageConfig) -> Result<(), Fallacy> {
+    let max_backups = config.max_backups();
+    if max_backups == 0 {
+        return Ok(());
+    }
+
+    let backup_dir = config.backup_dir();
+    std::fs::create_dir_all(&backup_dir)?;
+    let name = format!("{}{}.yaml", PREFIX, Local::now().format(TIME_FORMAT));
+    std::fs::write(backup_dir.join(name), content)?;
+
+    let regular = list(config)?
+        .into_iter()
+        .filter(|path| matches!(parse(path), Some((_, None))));
+    for old in regular.skip(max_backups) {
+        std::fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Copy the metadata file into the backup directory before upgrading its
+/// layout from `version`. These backups are kept regardless of `max_backups`.
+/// Returns the path to the backup.
+pub fn create_before_migration(
+    metadata: &Path,
+    version: u64,
+    config: &StorageConfig,
+) -> Result<PathBuf, Fallacy> {
+    let backup_dir = config.backup_dir();
+    std::fs::create_dir_all(&backup_dir)?;
+    let name = format!(
+        "{}v{}-{}.yaml",
+        PREFIX,
+        version,
+        Local::now().format(TIME_FORMAT)
+    );
+    let path = backup_dir.join(name);
+    std::fs::copy(metadata, &path)?;
+    Ok(path)
+}
+
+/// Backups in the backup directory, newest first.
+pub fn list(config: &StorageConfig) -> Result<Vec<PathBuf>, Fallacy> {
+    let backup_dir = config.backup_dir();
+    if !backup_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(backup_dir)? {
+        let path = entry?.path();
+        if let Some((time, _)) = parse(&path) {
+            backups.push((time, path));
+        }
+    }
+    backups.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The time of the backup in a human-readable form, parsed from the file name.
+pub fn timestamp(path: &Path) -> Option<String> {
+    let (time, version) = parse(path)?;
+    let time = time.format("%Y-%m-%d %H:%M:%S");
+    Some(match version {
+        Some(version) => format!("{} before upgrading from version {}", time, version),
+        None => time.to_string(),
+    })
+}
+
+/// The time of the backup, and the layout version it was upgraded from if
+/// it was taken before an upgrade.
+fn parse(path: &Path) -> Option<(NaiveDateTime, Option<u64>)> {
+    let stem = path.file_name()?.to_str()?.strip_suffix(".yaml")?;
+    let stem = stem.strip_prefix(PREFIX)?;
+    let (version, time) = match stem.strip_prefix('v') {
+        Some(rest) => {
+            let (version, time) = rest.split_once('-')?;
+            (Some(version.parse().ok()?), time)
+        }
+        None => (None, stem),
+    };
+    let time = NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok()?;
+    Some((time, version))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp() {
+        assert_eq!(
+            timestamp(Path::new("/backups/metadata-20210903-142031.512.yaml")),
+            Some("2021-09-03 14:20:31".to_owned())
+        );
+        assert_eq!(
+            timestamp(Path::new("/backups/metadata-v1-20210901-100000.yaml")),
+            Some("2021-09-01 10:00:00 before upgrading from version 1".to_owned())
+        );
+        assert_eq!(timestamp(Path::new("/backups/metadata.yaml")), None);
+        assert_eq!(
+            timestamp(Path::new("/backups/metadata-vx-20210901-100000.yaml")),
+            None
+        );
+        assert_eq!(timestamp(Path::new("/backups/notes.txt")), None);
+    }
+
+    #[test]
+    fn migration_backups_listed_and_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = dir.path().join("metadata.yaml");
+        std::fs::write(&metadata, "papers: []").unwrap();
+        let mut config = crate::config::Config::default().storage;
+        config.backup_dir = Some(dir.path().join("backups"));
+        config.max_backups = Some(1);
+
+        // Backup names differ only by their time.
+        let pause = || std::thread::sleep(std::time::Duration::from_millis(5));
+        create(&metadata, &config).unwrap();
+        pause();
+        let migration = create_before_migration(&metadata, 1, &config).unwrap();
+        pause();
+        create(&metadata, &config).unwrap();
+
+        // Only the oldest regular backup is removed.
+        let backups = list(&config).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[1], migration);
+        assert!(timestamp(&backups[0]).is_some());
+        assert!(timestamp(&migration)
+            .unwrap()
+            .ends_with(" before upgrading from version 1"));
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use crate::paper::Paper;
+
+/// Three-way merge of paper lists by paper ID.
+/// `base` is the paperbase both sides started from, `ours` is the paperbase
+/// of this session, and `theirs` is the paperbase stored by someone else.
+/// Changes made on only one side are taken as is. When both sides changed
+/// the same field of a paper differently, our version is kept. Papers added
+/// on both sides with the same ID get new IDs from `next_id` on. Returns the
+/// merged papers and descriptions of the conflicts.
+pub fn merge(
+    base: &[Paper],
+    ours: &[Paper],
+    theirs: &[Paper],
+    next_id: u64,
+) -> (Vec<Paper>, Vec<String>) {
+    let base: HashMap<_, _> = base.iter().map(|p| (p.id, p)).collect();
+    let their_map: HashMap<_, _> = theirs.iter().map(|p| (p.id, p)).collect();
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+
+    // Papers in the other paperbase keep their order.
+    for their in theirs {
+        let our = ours.iter().find(|p| p.id == their.id);
+        match base.get(&their.id) {
+            Some(base) => {
+                let (paper, conflict) = merge_existing(base, our, Some(their));
+                merged.extend(paper);
+                conflicts.extend(conflict);
+            }
+            // They added the paper. If we added a different paper with the
+            // same ID, ours gets a new ID below.
+            None => merged.push(their.clone()),
+        }
+    }
+
+    // Papers only in our paperbase.
+    let mut next_id = ours
+        .iter()
+        .chain(theirs)
+        .map(|p| p.id + 1)
+        .chain([next_id])
+        .max()
+        .unwrap_or(1);
+    for our in ours {
+        match (base.get(&our.id), their_map.get(&our.id)) {
+            // They removed the paper.
+            (Some(base), None) => {
+                let (paper, conflict) = merge_existing(base, Some(our), None);
+                merged.extend(paper);
+                conflicts.extend(conflict);
+            }
+            // We added the paper.
+            (None, None) => merged.push(our.clone()),
+            // Both sides added a different paper with the same ID.
+            (None, Some(&their)) if their != our => {
+                let mut paper = our.clone();
+                paper.id = next_id;
+                next_id += 1;
+                conflicts.push(format!(
+                    "'{}': ID #{} was taken elsewhere. Its new ID is #{}.",
+                    paper.title, our.id, paper.id
+                ));
+                merged.push(paper);
+            }
+            // Already merged above.
+            _ => {}
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Three-way merge of a paper that was in `base`, where `None` means the
+/// paper was removed on that side. A paper removed on one side and modified
+/// on the other is kept, unless only the times it was opened or read
+/// changed. Returns the merged paper, or `None` if the paper
+/// should be removed, and a description of the conflict if any.
+pub fn merge_existing(
+    base: &Paper,
+    ours: Option<&Paper>,
+    theirs: Option<&Paper>,
+) -> (Option<Paper>, Option<String>) {
+    match (ours, theirs) {
+        (Some(our), Some(their)) => {
+            let (paper, fields) = merge_paper(base, our, their);
+            let conflict = if fields.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "'{}': kept {} from this session.",
+                    paper.title,
+                    fields.join(", ")
+                ))
+            };
+            (Some(paper), conflict)
+        }
+        (None, Some(their)) if base.without_access() != their.without_access() => (
+            Some(their.clone()),
+            Some(format!(
+                "'{}': removed in this session but modified elsewhere. Kept it.",
+                their.title
+            )),
+        ),
+        (Some(our), None) if base.without_access() != our.without_access() => (
+            Some(our.clone()),
+            Some(format!(
+                "'{}': removed elsewhere but modified in this session. Kept it.",
+                our.title
+            )),
+        ),
+        _ => (None, None),
+    }
+}
+
+/// Merge each field of a paper. Returns the merged paper and the names of
+/// conflicting fields.
+fn merge_paper(base: &Paper, ours: &Paper, theirs: &Paper) -> (Paper, Vec<&'static str>) {
+    let mut merged = ours.clone();
+    let mut conflicts = Vec::new();
+
+    macro_rules! merge_field {
+        ($field:ident, $name:expr) => {
+            if ours.$field != theirs.$field {
+                if ours.$field == base.$field {
+                    merged.$field = theirs.$field.clone();
+                } else if theirs.$field != base.$field {
+                    conflicts.push($name);
+                }
+            }
+        };
+    }
+    merge_field!(title, "title");
+    merge_field!(nickname, "nickname");
+    merge_field!(authors, "authors");
+    merge_field!(venue, "venue");
+    merge_field!(year, "year");
+    merge_field!(filepath, "filepath");
+    merge_field!(labels, "labels");
+    merge_field!(notepath, "notepath");
+    merge_field!(fields, "fields");
+    merge_field!(added, "added");
+    merge_field!(modified, "modified");
+    merge_field!(opened, "opened");
+    merge_field!(read, "read");
+
+    (merged, conflicts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::paper;
+
+    fn summary(papers: &[Paper]) -> Vec<(u64, &str, &str)> {
+        papers
+            .iter()
+            .map(|p| (p.id, p.title.as_str(), p.venue.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn one_sided_changes() {
+        let base = vec![paper(1, "A").at("OSDI"), paper(2, "B").at("SOSP")];
+        let ours = vec![paper(1, "A").at("NSDI"), paper(3, "C").at("ATC")];
+        let theirs = vec![paper(2, "B2").at("SOSP"), paper(4, "D").at("ICML")];
+        let (merged, conflicts) = merge(&base, &ours, &theirs, 1);
+        // B was removed by us and modified by them, so it is kept.
+        // A was removed by them and modified by us, so it is kept.
+        // Papers added on either side are kept.
+        assert_eq!(
+            summary(&merged),
+            vec![
+                (2, "B2", "SOSP"),
+                (4, "D", "ICML"),
+                (1, "A", "NSDI"),
+                (3, "C", "ATC")
+            ]
+        );
+        assert_eq!(conflicts.len(), 2);
+    }
+
+    #[test]
+    fn field_merge() {
+        let base = vec![paper(1, "A").at("OSDI")];
+        let ours = vec![paper(1, "A2").at("OSDI")];
+        let theirs = vec![paper(1, "A").at("NSDI")];
+        let (merged, conflicts) = merge(&base, &ours, &theirs, 1);
+        assert_eq!(summary(&merged), vec![(1, "A2", "NSDI")]);
+        assert!(conflicts.is_empty());
+
+        let theirs = vec![paper(1, "A3").at("NSDI")];
+        let (merged, conflicts) = merge(&base, &ours, &theirs, 1);
+        assert_eq!(summary(&merged), vec![(1, "A2", "NSDI")]);
+        assert_eq!(conflicts.len(), 1);
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut base = paper(1, "A");
+        base.added = Some("2021-01-01T00:00:00+00:00".to_owned());
+        let mut ours = base.clone();
+        ours.opened = Some("2021-02-01T00:00:00+00:00".to_owned());
+        let mut theirs = base.clone();
+        theirs.modified = Some("2021-03-01T00:00:00+00:00".to_owned());
+        let (merged, conflicts) = merge(&[base], &[ours.clone()], &[theirs.clone()], 1);
+        assert_eq!(merged[0].opened, ours.opened);
+        assert_eq!(merged[0].modified, theirs.modified);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn removed_after_access() {
+        let base = vec![paper(1, "A"), paper(2, "B")];
+        // They opened A, and we removed it.
+        let mut theirs = base.clone();
+        theirs[0].opened = Some("2021-02-01T00:00:00+00:00".to_owned());
+        let ours = vec![paper(2, "B")];
+        let (merged, conflicts) = merge(&base, &ours, &theirs, 1);
+        assert_eq!(summary(&merged), vec![(2, "B", "")]);
+        assert!(conflicts.is_empty());
+
+        // We read B's note, and they removed it.
+        let mut ours = base.clone();
+        ours[1].read = Some("2021-02-01T00:00:00+00:00".to_owned());
+        let theirs = vec![paper(1, "A")];
+        let (merged, conflicts) = merge(&base, &ours, &theirs, 1);
+        assert_eq!(summary(&merged), vec![(1, "A", "")]);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn id_collision() {
+        let base = vec![paper(1, "A").at("OSDI")];
+        let ours = vec![paper(1, "A").at("OSDI"), paper(2, "B").at("SOSP")];
+        let theirs = vec![paper(1, "A").at("OSDI"), paper(2, "C").at("ATC")];
+        let (merged, conflicts) = merge(&base, &ours, &theirs, 1);
+        assert_eq!(
+            summary(&merged),
+            vec![(1, "A", "OSDI"), (2, "C", "ATC"), (3, "B", "SOSP")]
+        );
+        assert_eq!(conflicts.len(), 1);
+    }
+}
@@ -1,35 +1,215 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
+use fs2::FileExt;
+use tempfile::NamedTempFile;
+
+pub mod backup;
 mod filter;
+mod merge;
+mod schema;
+mod sqlite;
+pub mod trash;
+mod undo;
 pub use crate::state::filter::{FilterInst, FilterState};
+pub use crate::state::trash::Trash;
+pub use crate::state::undo::UndoHistory;
 
+use crate::config::{StorageBackend, StorageConfig};
 use crate::error::Fallacy;
-use crate::paper::Paper;
+use crate::filter::PaperFilter;
+use crate::paper::{Event, Paper};
+use crate::utils::select;
 
 #[derive(Default, Debug)]
 pub struct State {
     pub papers: Vec<Paper>,
     pub filters: FilterState,
+    pub undo: UndoHistory,
+    pub trash: Trash,
+    /// The ID for the next paper, unless a paper has a larger ID.
+    next_id: u64,
+    /// Paper ID -> index in `papers`. Rebuilt when it is found to be stale.
+    index: RefCell<HashMap<u64, usize>>,
+    /// The stored paper metadata as of the last load or store.
+    synced: Option<Snapshot>,
+    /// Whether the SQLite database was backed up in this session.
+    backed_up: bool,
+}
+
+/// What the metadata file looked like when this session last read or wrote it.
+/// Used to detect changes made by other sessions. With the SQLite backend,
+/// `papers` is used to find papers that changed since, and `generation` to
+/// find whether another session wrote to the database.
+#[derive(Debug)]
+struct Snapshot {
+    modified: Option<SystemTime>,
+    hash: u64,
+    papers: Vec<Paper>,
+    next_id: u64,
+    generation: u64,
+}
+
+impl Snapshot {
+    fn new(state_path: &Path, content: &str, papers: &[Paper]) -> Self {
+        Self {
+            modified: modified(state_path),
+            hash: hash(content),
+            papers: papers.to_vec(),
+            next_id: 0,
+            generation: 0,
+        }
+    }
+
+    fn of_database(papers: &[Paper], next_id: u64, generation: u64) -> Self {
+        Self {
+            modified: None,
+            hash: 0,
+            papers: papers.to_vec(),
+            next_id,
+            generation,
+        }
+    }
+
+    /// Take the given papers, cloning only those that changed.
+    fn update(&mut self, papers: &[Paper]) {
+        let mut old: HashMap<_, _> = std::mem::take(&mut self.papers)
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+        self.papers = papers
+            .iter()
+            .map(|paper| match old.remove(&paper.id) {
+                Some(old) if &old == paper => old,
+                _ => paper.clone(),
+            })
+            .collect();
+    }
+
+    /// Whether the file was changed since the snapshot. The content is only
+    /// compared if the modification time changed.
+    fn is_outdated(&self, state_path: &Path, content: &str) -> bool {
+        self.modified != modified(state_path) && self.hash != hash(content)
+    }
+
+    /// Whether the papers differ from the snapshot only in the times they
+    /// were opened or read.
+    fn only_accessed(&self, papers: &[Paper]) -> bool {
+        self.papers.len() == papers.len()
+            && self
+                .papers
+                .iter()
+                .zip(papers.iter())
+                .all(|(old, new)| old == new || old.without_access() == new.without_access())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Take an advisory lock on the file next to the metadata file, so that only
+/// one session reads and writes the metadata file at a time. The lock is
+/// released when the returned file is dropped.
+fn lock(state_path: &Path) -> Result<File, Fallacy> {
+    let mut lock_path = state_path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_path = PathBuf::from(lock_path);
+    let file = match File::create(&lock_path) {
+        Ok(f) => f,
+        Err(e) => return Err(Fallacy::StateStoreFailed(lock_path, e)),
+    };
+
+    // Other sessions hold the lock only while storing, so wait a bit.
+    for _ in 0..50 {
+        if file.try_lock_exclusive().is_ok() {
+            return Ok(file);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Err(Fallacy::StateLocked(lock_path))
+}
+
+/// Write to a temporary file in the same directory and rename it, so that a
+/// crash in the middle leaves either the old or the new file intact.
+/// The directory is synced too, so that the rename itself survives a crash.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(content.as_bytes())?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+    // Directories cannot be opened as files on Windows, where renames are
+    // flushed with the file.
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+    Ok(())
 }
 
 impl State {
-    pub fn load(state_path: &Path) -> Result<Self, Fallacy> {
+    /// Load paper metadata. Files written in an older layout are backed up
+    /// and upgraded. They are written in the current layout when stored.
+    pub fn load(config: &StorageConfig) -> Result<Self, Fallacy> {
+        if config.backend() == StorageBackend::Sqlite {
+            let (document, generation) = sqlite::load(&config.paper_database())?;
+            let mut state = Self {
+                synced: Some(Snapshot::of_database(
+                    &document.papers,
+                    document.next_id,
+                    generation,
+                )),
+                papers: document.papers,
+                next_id: document.next_id,
+                ..Default::default()
+            };
+            state.assign_ids();
+            return Ok(state);
+        }
+
+        let state_path = &config.paper_metadata;
         if state_path.exists() {
-            // Open file for reading.
-            let file = match File::open(state_path) {
-                Ok(f) => f,
+            // Read the file.
+            let content = match std::fs::read_to_string(state_path) {
+                Ok(content) => content,
                 Err(e) => return Err(Fallacy::StateLoadFailed(state_path.to_owned(), e)),
             };
 
             // Load state from the file.
-            match serde_yaml::from_reader(file) {
-                Ok(papers) => Ok(Self {
-                    papers,
-                    filters: FilterState::default(),
-                }),
-                Err(e) => Err(Fallacy::StateDeserializeFailed(state_path.to_owned(), e)),
+            let (document, version) = schema::parse(state_path, &content)?;
+            if version < schema::VERSION {
+                let backup = backup::create_before_migration(state_path, version, config)?;
+                eprintln!(
+                    "Upgraded paper metadata from version {} to {}. The old file is backed up to {:?}.",
+                    version,
+                    schema::VERSION,
+                    backup
+                );
             }
+            let synced = Some(Snapshot::new(state_path, &content, &document.papers));
+            let mut state = Self {
+                papers: document.papers,
+                next_id: document.next_id,
+                synced,
+                ..Default::default()
+            };
+            state.assign_ids();
+            Ok(state)
         } else {
             // Try creating the file to see if we have access.
             if let Some(dir) = state_path.parent() {
@@ -47,37 +227,520 @@ impl State {
         }
     }
 
-    pub fn store(&self, state_path: &Path) -> Result<(), Fallacy> {
-        let emergency_button = |state: &State| {
-            eprintln!("Could not save state. Dumping to stderr!");
-            eprintln!("== Debug string ==\n{:#?}\n", state);
-            match serde_yaml::to_string(&state.papers) {
-                Ok(s) => eprintln!("== Serialized string ==\n{}", s),
-                Err(e) => eprintln!("== Serialization error ==\n{}", e),
-            }
+    /// Read papers from a metadata file other than the one in use, e.g. a backup.
+    pub fn read(path: &Path) -> Result<Vec<Paper>, Fallacy> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(Fallacy::StateLoadFailed(path.to_owned(), e)),
         };
+        Ok(schema::parse(path, &content)?.0.papers)
+    }
 
-        // Create/truncate the file first.
-        if let Some(dir) = state_path.parent() {
-            if let Err(e) = std::fs::create_dir_all(dir) {
-                return Err(Fallacy::StateStoreFailed(dir.to_owned(), e));
+    /// Write papers and the ID for the next paper to a metadata file other
+    /// than the one in use, e.g. when converting from another backend.
+    pub fn write(path: &Path, papers: &[Paper], next_id: u64) -> Result<(), Fallacy> {
+        let serialized = match schema::serialize(papers, next_id) {
+            Ok(s) => s,
+            Err(e) => return Err(Fallacy::StateSerializeFailed(path.to_owned(), e)),
+        };
+        match write_atomic(path, &serialized) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Fallacy::StateStoreFailed(path.to_owned(), e)),
+        }
+    }
+
+    /// Read all papers from the SQLite database at the given path.
+    pub fn read_database(path: &Path) -> Result<Vec<Paper>, Fallacy> {
+        Ok(sqlite::load(path)?.0.papers)
+    }
+
+    /// Replace all papers and the ID for the next paper in the SQLite
+    /// database at the given path.
+    pub fn write_database(path: &Path, papers: &[Paper], next_id: u64) -> Result<(), Fallacy> {
+        sqlite::write_all(path, papers, next_id)
+    }
+
+    /// Run the filter as a database query, if paper metadata is stored in
+    /// SQLite and this session has no unsaved changes. Returns the IDs of
+    /// matching papers, or `None` if the filter should be run in memory.
+    pub fn query(&self, filter: &PaperFilter, config: &StorageConfig) -> Option<Vec<u64>> {
+        if config.backend() != StorageBackend::Sqlite {
+            return None;
+        }
+        match &self.synced {
+            Some(synced) if synced.papers == self.papers => {}
+            _ => return None,
+        }
+        let ids = sqlite::query(&config.paper_database(), filter).ok()??;
+        // Another session may have added papers that are not loaded yet.
+        Some(
+            ids.into_iter()
+                .filter(|&id| self.position(id).is_some())
+                .collect(),
+        )
+    }
+
+    /// Give IDs to papers that don't have one, e.g. papers stored by older
+    /// versions, or that share their ID with an earlier paper.
+    pub fn assign_ids(&mut self) {
+        let mut seen = HashSet::new();
+        let mut next_id = self.next_id();
+        for paper in self.papers.iter_mut() {
+            if paper.id == 0 || !seen.insert(paper.id) {
+                paper.id = next_id;
+                seen.insert(next_id);
+                next_id += 1;
             }
         }
-        let file = match File::create(state_path) {
-            Ok(f) => f,
+        self.next_id = self.next_id();
+    }
+
+    /// The ID for the next paper. IDs start from 1 and are never reused,
+    /// even after papers are deleted from the trash.
+    pub fn next_id(&self) -> u64 {
+        let trashed = self.trash.entries.iter().map(|e| &e.paper);
+        self.papers
+            .iter()
+            .chain(trashed)
+            .map(|p| p.id + 1)
+            .chain([self.next_id, 1])
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Add a paper to the paperbase and return its new ID.
+    /// The time the paper was added is recorded.
+    pub fn add(&mut self, mut paper: Paper) -> u64 {
+        let id = self.next_id();
+        paper.id = id;
+        paper.record(Event::Added);
+        self.papers.push(paper);
+        self.next_id = id + 1;
+        id
+    }
+
+    /// Add a paper that used to be in the paperbase, keeping its ID if no other
+    /// paper has it. Returns the ID of the paper.
+    pub fn insert(&mut self, mut paper: Paper) -> u64 {
+        if paper.id == 0 || self.position(paper.id).is_some() {
+            paper.id = self.next_id();
+        }
+        let id = paper.id;
+        let ind = self
+            .papers
+            .iter()
+            .position(|p| p.id > id)
+            .unwrap_or(self.papers.len());
+        self.papers.insert(ind, paper);
+        self.next_id = self.next_id();
+        id
+    }
+
+    /// Remove the paper with the given ID, if it exists.
+    pub fn remove(&mut self, id: u64) -> Option<Paper> {
+        let ind = self.position(id)?;
+        Some(self.papers.remove(ind))
+    }
+
+    /// IDs of all papers, in the order they were added.
+    pub fn ids(&self) -> Vec<u64> {
+        self.papers.iter().map(|p| p.id).collect()
+    }
+
+    /// The index in `papers` of the paper with the given ID, if it exists.
+    pub fn position(&self, id: u64) -> Option<usize> {
+        let cached = self.index.borrow().get(&id).copied();
+        if let Some(ind) = cached {
+            if self.papers.get(ind).is_some_and(|p| p.id == id) {
+                return Some(ind);
+            }
+        }
+        // Papers were added, removed, or reloaded since the index was built.
+        let index: HashMap<_, _> = self
+            .papers
+            .iter()
+            .enumerate()
+            .map(|(ind, p)| (p.id, ind))
+            .collect();
+        let ind = index.get(&id).copied();
+        *self.index.borrow_mut() = index;
+        ind
+    }
+
+    /// The paper with the given ID. Paper lists given through pipe or found
+    /// in the database may hold IDs of papers that no longer exist.
+    pub fn paper(&self, id: u64) -> Option<&Paper> {
+        let ind = self.position(id)?;
+        self.papers.get(ind)
+    }
+
+    /// Mutable version of `paper`.
+    pub fn paper_mut(&mut self, id: u64) -> Option<&mut Paper> {
+        let ind = self.position(id)?;
+        self.papers.get_mut(ind)
+    }
+
+    /// Store paper metadata. The file is replaced atomically, so a crash in the
+    /// middle leaves either the old or the new metadata intact. The old file is
+    /// backed up first if its content changes.
+    /// If another session changed the file since it was last loaded or stored,
+    /// the user chooses between merging, reloading, and overwriting.
+    pub fn store(&mut self, config: &StorageConfig) -> Result<(), Fallacy> {
+        if config.backend() == StorageBackend::Sqlite {
+            return self.store_database(config);
+        }
+
+        let state_path = &config.paper_metadata;
+        let _lock = lock(state_path)?;
+        let existing = std::fs::read_to_string(state_path).unwrap_or_default();
+
+        // Check for changes made by other sessions.
+        if let Some(synced) = &self.synced {
+            if synced.is_outdated(state_path, &existing) {
+                match schema::parse(state_path, &existing) {
+                    Ok((theirs, _)) => {
+                        if !self.resolve(theirs)? {
+                            self.synced = Some(Snapshot::new(state_path, &existing, &self.papers));
+                            return Ok(());
+                        }
+                    }
+                    // Never overwrite metadata written by a newer version.
+                    Err(e @ Fallacy::StateNewerVersion(..)) => return Err(e),
+                    Err(e) => eprintln!(
+                        "Paper metadata changed by another session could not be parsed ({}). Overwriting it.",
+                        e
+                    ),
+                }
+            }
+        }
+
+        // Serialize state.
+        let serialized = match schema::serialize(&self.papers, self.next_id()) {
+            Ok(s) => s,
             Err(e) => {
-                emergency_button(self);
-                return Err(Fallacy::StateStoreFailed(state_path.to_owned(), e));
+                self.emergency_button();
+                return Err(Fallacy::StateSerializeFailed(state_path.to_owned(), e));
             }
         };
 
-        // Store state into the file.
-        match serde_yaml::to_writer(file, &self.papers) {
-            Ok(()) => Ok(()),
+        // Nothing to do if the file is up to date.
+        if existing == serialized {
+            self.synced = Some(Snapshot::new(state_path, &existing, &self.papers));
+            return Ok(());
+        }
+
+        // Back up the old file, unless papers were only opened or read.
+        // Failing to do so should not prevent saving.
+        let access_only = match &self.synced {
+            Some(synced) if !synced.is_outdated(state_path, &existing) => {
+                synced.only_accessed(&self.papers)
+            }
+            _ => false,
+        };
+        if !existing.is_empty() && !access_only {
+            if let Err(e) = backup::create(state_path, config) {
+                eprintln!("Could not back up paper metadata: {}", e);
+            }
+        }
+
+        if let Err(e) = write_atomic(state_path, &serialized) {
+            self.emergency_button();
+            return Err(Fallacy::StateStoreFailed(state_path.to_owned(), e));
+        }
+        self.synced = Some(Snapshot::new(state_path, &serialized, &self.papers));
+        Ok(())
+    }
+
+    /// Store paper metadata in the SQLite database. Only papers changed in
+    /// this session are read and written. If another session wrote to the
+    /// database in the meantime, all papers are loaded again.
+    fn store_database(&mut self, config: &StorageConfig) -> Result<(), Fallacy> {
+        let path = config.paper_database();
+        let next_id = self.next_id();
+        let (generation, unchanged, only_accessed) = match &self.synced {
+            Some(synced) => (
+                synced.generation,
+                synced.next_id == next_id && synced.papers == self.papers,
+                synced.next_id == next_id && synced.only_accessed(&self.papers),
+            ),
+            None => (0, false, false),
+        };
+
+        // Nothing to write. Just check whether another session wrote.
+        if unchanged {
+            if sqlite::generation(&path)? != generation {
+                self.reload_database(&path)?;
+            }
+            return Ok(());
+        }
+
+        // Back up the database before this session first changes more than
+        // access times, so that `restore` works with both backends. Failing
+        // to do so should not prevent saving.
+        if !self.backed_up && !only_accessed {
+            if let Err(e) = Self::backup_database(&path, config) {
+                eprintln!("Could not back up paper metadata: {}", e);
+            }
+            self.backed_up = true;
+        }
+
+        let base = self
+            .synced
+            .as_ref()
+            .map(|synced| &synced.papers[..])
+            .unwrap_or_default();
+        let stored = match sqlite::store(&path, base, &mut self.papers, next_id, generation) {
+            Ok(stored) => stored,
             Err(e) => {
-                emergency_button(self);
-                Err(Fallacy::StateSerializeFailed(state_path.to_owned(), e))
+                self.emergency_button();
+                return Err(e);
             }
+        };
+        if !stored.conflicts.is_empty() {
+            eprintln!("Conflicts:");
+            for conflict in stored.conflicts {
+                eprintln!("  {}", conflict);
+            }
+        }
+
+        if stored.outdated {
+            return self.reload_database(&path);
+        }
+        match &mut self.synced {
+            Some(synced) => {
+                synced.update(&self.papers);
+                synced.next_id = next_id;
+                synced.generation = stored.generation;
+            }
+            None => {
+                self.synced = Some(Snapshot::of_database(
+                    &self.papers,
+                    next_id,
+                    stored.generation,
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Load all papers from the SQLite database again, after another session
+    /// wrote to it.
+    fn reload_database(&mut self, path: &Path) -> Result<(), Fallacy> {
+        let (document, generation) = sqlite::load(path)?;
+        if document.papers != self.papers {
+            eprintln!("Reloaded paper metadata changed by another session.");
+            self.papers = document.papers;
         }
+        self.next_id = self.next_id.max(document.next_id);
+        self.synced = Some(Snapshot::of_database(
+            &self.papers,
+            self.next_id(),
+            generation,
+        ));
+        Ok(())
+    }
+
+    /// Back up paper metadata the next time it is stored. With the SQLite
+    /// backend, the database is otherwise backed up only once per session.
+    pub fn request_backup(&mut self) {
+        self.backed_up = false;
+    }
+
+    fn backup_database(path: &Path, config: &StorageConfig) -> Result<(), Fallacy> {
+        let (document, _) = sqlite::load(path)?;
+        if document.papers.is_empty() {
+            return Ok(());
+        }
+        let serialized = schema::serialize(&document.papers, document.next_id)
+            .map_err(|e| Fallacy::StateSerializeFailed(path.to_owned(), e))?;
+        backup::save(serialized.as_bytes(), config)
+    }
+
+    fn emergency_button(&self) {
+        eprintln!("Could not save state. Dumping to stderr!");
+        eprintln!("== Debug string ==\n{:#?}\n", self);
+        match serde_yaml::to_string(&self.papers) {
+            Ok(s) => eprintln!("== Serialized string ==\n{}", s),
+            Err(e) => eprintln!("== Serialization error ==\n{}", e),
+        }
+    }
+
+    /// Reconcile with papers stored by another session. Returns whether our
+    /// papers should be written to the file.
+    fn resolve(&mut self, theirs: schema::Document) -> Result<bool, Fallacy> {
+        // IDs given out by either session are not reused.
+        self.next_id = self.next_id().max(theirs.next_id);
+        let theirs = theirs.papers;
+        let base = match &self.synced {
+            Some(synced) => &synced.papers,
+            None => return Ok(true),
+        };
+
+        // Nothing changed in this session. Just take theirs.
+        if &self.papers == base {
+            eprintln!("Reloaded paper metadata changed by another session.");
+            self.papers = theirs;
+            self.assign_ids();
+            return Ok(false);
+        }
+
+        let choice = select(
+            "Paper metadata was changed by another session:",
+            [
+                "Merge the changes",
+                "Reload, discarding changes in this session",
+                "Overwrite changes made elsewhere",
+            ]
+            .iter()
+            .copied(),
+        )?;
+        match choice {
+            0 => {
+                let (merged, conflicts) = merge::merge(base, &self.papers, &theirs, self.next_id);
+                if !conflicts.is_empty() {
+                    eprintln!("Conflicts:");
+                    for conflict in conflicts {
+                        eprintln!("  {}", conflict);
+                    }
+                }
+                self.papers = merged;
+                Ok(true)
+            }
+            1 => {
+                self.papers = theirs;
+                self.assign_ids();
+                Ok(false)
+            }
+            _ => Ok(true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::paper;
+
+    #[test]
+    fn write_atomic_replaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.yaml");
+        write_atomic(&path, "old").unwrap();
+        write_atomic(&path, "new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn assign_ids() {
+        let mut state = State {
+            papers: vec![paper(0, ""), paper(3, ""), paper(3, ""), paper(0, "")],
+            ..Default::default()
+        };
+        state.assign_ids();
+        assert_eq!(state.ids(), vec![4, 3, 5, 6]);
+    }
+
+    #[test]
+    fn ids_are_stable() {
+        let mut state = State::default();
+        let first = state.add(paper(0, ""));
+        let second = state.add(paper(0, ""));
+        let third = state.add(paper(0, ""));
+        assert_eq!((first, second, third), (1, 2, 3));
+        state.remove(second);
+        assert_eq!(state.paper(third).unwrap().id, 3);
+        assert_eq!(state.add(paper(0, "")), 4);
+    }
+
+    #[test]
+    fn reload_assigns_ids() {
+        let mut state = State {
+            papers: vec![paper(1, "")],
+            synced: Some(Snapshot::of_database(&[paper(1, "")], 2, 0)),
+            ..Default::default()
+        };
+        let theirs = schema::Document {
+            next_id: 2,
+            papers: vec![paper(1, ""), paper(0, ""), paper(1, "")],
+        };
+        assert!(!state.resolve(theirs).unwrap());
+        assert_eq!(state.ids(), vec![1, 2, 3]);
+        assert_eq!(state.next_id(), 4);
+    }
+
+    #[test]
+    fn database_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StorageConfig {
+            backend: Some(StorageBackend::Sqlite),
+            paper_database: Some(dir.path().join("metadata.sqlite3")),
+            backup_dir: Some(dir.path().join("backups")),
+            ..Default::default()
+        };
+        let mut ours = State::load(&config).unwrap();
+        let mut theirs = State::load(&config).unwrap();
+        ours.add(paper(0, "A"));
+        ours.store(&config).unwrap();
+        let generation = ours.synced.as_ref().unwrap().generation;
+
+        // Nothing changed, so nothing is written.
+        ours.store(&config).unwrap();
+        assert_eq!(
+            sqlite::generation(&config.paper_database()).unwrap(),
+            generation
+        );
+
+        // Changes made elsewhere are loaded even without changes here.
+        theirs.store(&config).unwrap();
+        assert_eq!(theirs.ids(), vec![1]);
+        theirs.add(paper(0, "B"));
+        theirs.store(&config).unwrap();
+        ours.store(&config).unwrap();
+        assert_eq!(ours.ids(), vec![1, 2]);
+
+        // Only the first change in a session is backed up.
+        assert_eq!(backup::list(&config).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn access_not_backed_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StorageConfig {
+            paper_metadata: dir.path().join("metadata.yaml"),
+            backup_dir: Some(dir.path().join("backups")),
+            ..Default::default()
+        };
+        let mut state = State::load(&config).unwrap();
+        state.add(paper(0, "A"));
+        state.store(&config).unwrap();
+
+        state.papers[0].read = Some("2021-09-03T14:20:31+09:00".to_owned());
+        state.store(&config).unwrap();
+        assert!(backup::list(&config).unwrap().is_empty());
+
+        state.papers[0].title = "B".to_owned();
+        state.store(&config).unwrap();
+        assert_eq!(backup::list(&config).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ids_not_reused() {
+        let mut state = State::default();
+        state.add(paper(0, ""));
+        let second = state.add(paper(0, ""));
+        // Removed and then deleted from the trash.
+        state.remove(second);
+        assert!(state.paper(second).is_none());
+        assert_eq!(state.add(paper(0, "")), 3);
+
+        // The counter comes from the stored paperbase.
+        let mut state = State {
+            papers: vec![paper(1, "")],
+            next_id: 7,
+            ..Default::default()
+        };
+        state.assign_ids();
+        assert_eq!(state.add(paper(0, "")), 7);
     }
 }
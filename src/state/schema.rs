@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::error::Fallacy;
+use crate::paper::Paper;
+
+/// The version of the metadata file layout written by this version of reason.
+/// Bump this and add a migration step whenever the layout changes.
+pub const VERSION: u64 = 3;
+
+/// A step that upgrades the layout by one version.
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to version `i + 2`.
+static MIGRATIONS: &[Migration] = &[v1_to_v2, v2_to_v3];
+
+#[derive(Serialize)]
+struct DocumentRef<'p> {
+    version: u64,
+    next_id: u64,
+    papers: &'p [Paper],
+}
+
+/// The content of a metadata file.
+#[derive(Deserialize, Default, Debug, PartialEq)]
+pub struct Document {
+    /// The ID for the next paper. IDs are never reused, even after the
+    /// papers that had them are deleted.
+    pub next_id: u64,
+    pub papers: Vec<Paper>,
+}
+
+/// Serialize papers in the current layout.
+pub fn serialize(papers: &[Paper], next_id: u64) -> Result<String, serde_yaml::Error> {
+    serde_yaml::to_string(&DocumentRef {
+        version: VERSION,
+        next_id,
+        papers,
+    })
+}
+
+/// Parse the content of a metadata file, upgrading older layouts.
+/// Returns the document and the version of the layout the content was in.
+pub fn parse(path: &Path, content: &str) -> Result<(Document, u64), Fallacy> {
+    // An empty file, e.g. one created on the first run.
+    if content.trim().is_empty() {
+        return Ok((Document::default(), VERSION));
+    }
+
+    let mut value: Value = match serde_yaml::from_str(content) {
+        Ok(value) => value,
+        Err(e) => return Err(Fallacy::StateDeserializeFailed(path.to_owned(), e)),
+    };
+    let version = version(&value)
+        .ok_or_else(|| Fallacy::StateMigrationFailed(path.to_owned(), "No version.".to_owned()))?;
+    if version > VERSION {
+        return Err(Fallacy::StateNewerVersion(path.to_owned(), version));
+    }
+
+    // Upgrade one version at a time.
+    for migrate in MIGRATIONS.iter().skip(version as usize - 1) {
+        value = match migrate(value) {
+            Ok(value) => value,
+            Err(e) => return Err(Fallacy::StateMigrationFailed(path.to_owned(), e)),
+        };
+    }
+
+    match serde_yaml::from_value::<Document>(value) {
+        Ok(document) => Ok((document, version)),
+        Err(e) => Err(Fallacy::StateDeserializeFailed(path.to_owned(), e)),
+    }
+}
+
+/// The version of the layout of a parsed metadata file.
+fn version(value: &Value) -> Option<u64> {
+    match value {
+        // Version 1 was a bare list of papers.
+        Value::Sequence(_) => Some(1),
+        Value::Mapping(map) => map
+            .get(&Value::from("version"))
+            .and_then(|v| v.as_u64())
+            .filter(|&v| v >= 1),
+        _ => None,
+    }
+}
+
+/// Version 2 puts the list of papers under the 'papers' key, next to the
+/// 'version' key.
+fn v1_to_v2(value: Value) -> Result<Value, String> {
+    let mut map = Mapping::new();
+    map.insert(Value::from("version"), Value::from(2));
+    map.insert(Value::from("papers"), value);
+    Ok(Value::Mapping(map))
+}
+
+/// Version 3 keeps the ID for the next paper in the 'next_id' key, so that
+/// IDs of deleted papers are not reused.
+fn v2_to_v3(mut value: Value) -> Result<Value, String> {
+    let map = value.as_mapping_mut().ok_or("Not a mapping.")?;
+    let max_id = map
+        .get(&Value::from("papers"))
+        .and_then(|papers| papers.as_sequence())
+        .ok_or("No papers.")?
+        .iter()
+        .filter_map(|paper| paper.get("id").and_then(|id| id.as_u64()))
+        .max()
+        .unwrap_or(0);
+    map.insert(Value::from("version"), Value::from(3));
+    map.insert(Value::from("next_id"), Value::from(max_id + 1));
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::paper;
+
+    fn parse_titles(content: &str) -> Result<(Vec<String>, u64), Fallacy> {
+        let (document, version) = parse(Path::new("metadata.yaml"), content)?;
+        Ok((
+            document.papers.into_iter().map(|p| p.title).collect(),
+            version,
+        ))
+    }
+
+    #[test]
+    fn bare_list() {
+        let content = "---\n- title: A\n  nickname: ~\n  authors: [X]\n  venue: V\n  year: \"2020\"\n  filepath: ~\n  labels: []\n  notepath: ~\n";
+        assert_eq!(parse_titles(content).unwrap(), (vec!["A".to_owned()], 1));
+    }
+
+    #[test]
+    fn current() {
+        let papers = vec![paper(3, "A")];
+        let content = serialize(&papers, 8).unwrap();
+        assert!(content.contains("version: 3"));
+        let (parsed, version) = parse(Path::new("metadata.yaml"), &content).unwrap();
+        assert_eq!(
+            (parsed, version),
+            (Document { next_id: 8, papers }, VERSION)
+        );
+    }
+
+    #[test]
+    fn next_id_from_papers() {
+        let content = "version: 2\npapers:\n- id: 3\n  title: A\n  nickname: ~\n  authors: [X]\n  venue: V\n  year: \"2020\"\n  filepath: ~\n  labels: []\n  notepath: ~\n";
+        let (document, version) = parse(Path::new("metadata.yaml"), content).unwrap();
+        assert_eq!((document.next_id, version), (4, 2));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(parse_titles("").unwrap(), (vec![], VERSION));
+    }
+
+    #[test]
+    fn newer() {
+        assert!(matches!(
+            parse_titles("version: 100\npapers: []\n"),
+            Err(Fallacy::StateNewerVersion(_, 100))
+        ));
+        assert!(matches!(
+            parse_titles("papers: []\n"),
+            Err(Fallacy::StateMigrationFailed(..))
+        ));
+    }
+}
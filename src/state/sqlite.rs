@@ -0,0 +1,689 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::DateTime;
+use regex::Regex;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+
+use crate::error::Fallacy;
+use crate::filter::{parse_year, Field, FilterExpr, PaperFilter, TimeCmp, YearCmp};
+use crate::paper::Paper;
+use crate::state::merge;
+use crate::state::schema::Document;
+
+/// The version of the database schema written by this version of reason,
+/// kept in `PRAGMA user_version`.
+const VERSION: u64 = 4;
+
+/// The current schema, used to create new databases.
+static SCHEMA: &str = "
+CREATE TABLE papers (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    nickname TEXT,
+    venue TEXT NOT NULL,
+    year TEXT NOT NULL,
+    filepath TEXT,
+    notepath TEXT,
+    added TEXT,
+    modified TEXT,
+    opened TEXT,
+    read TEXT
+);
+CREATE TABLE authors (
+    paper_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (paper_id, position)
+);
+CREATE TABLE labels (
+    paper_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    PRIMARY KEY (paper_id, label)
+);
+CREATE TABLE fields (
+    paper_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (paper_id, key)
+);
+CREATE TABLE meta (
+    key TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+INSERT INTO meta (key, value) VALUES ('next_id', 1);
+CREATE INDEX authors_name ON authors (name);
+CREATE INDEX labels_label ON labels (label);
+";
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to version `i + 2`.
+static MIGRATIONS: &[&str] = &[
+    // Version 2 added custom fields.
+    "CREATE TABLE fields (
+        paper_id INTEGER NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (paper_id, key)
+    );",
+    // Version 3 added timestamps.
+    "ALTER TABLE papers ADD COLUMN added TEXT;
+     ALTER TABLE papers ADD COLUMN modified TEXT;
+     ALTER TABLE papers ADD COLUMN opened TEXT;
+     ALTER TABLE papers ADD COLUMN read TEXT;",
+    // Version 4 keeps the ID for the next paper, so that IDs are not reused.
+    "CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    INSERT INTO meta (key, value) SELECT 'next_id', COALESCE(MAX(id), 0) + 1 FROM papers;",
+];
+
+/// Open the database, creating or upgrading tables as needed.
+fn open(path: &Path) -> Result<Connection, Fallacy> {
+    let error = |e| Fallacy::StateDatabaseFailed(path.to_owned(), e);
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            return Err(Fallacy::StateStoreFailed(dir.to_owned(), e));
+        }
+    }
+    let mut conn = Connection::open(path).map_err(error)?;
+    // Other sessions hold the database only while storing, so wait a bit.
+    conn.busy_timeout(Duration::from_secs(5)).map_err(error)?;
+
+    let version: u64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(error)?;
+    if version > VERSION {
+        return Err(Fallacy::StateNewerVersion(path.to_owned(), version));
+    }
+    if version < VERSION {
+        let tx = conn.transaction().map_err(error)?;
+        if version == 0 {
+            tx.execute_batch(SCHEMA).map_err(error)?;
+        } else {
+            for migration in MIGRATIONS.iter().skip(version as usize - 1) {
+                tx.execute_batch(migration).map_err(error)?;
+            }
+        }
+        tx.pragma_update(None, "user_version", VERSION)
+            .map_err(error)?;
+        tx.commit().map_err(error)?;
+    }
+    Ok(conn)
+}
+
+/// Load all papers, in the order of their IDs, and the ID for the next paper.
+/// Also returns the generation of the database, see `generation`.
+pub fn load(path: &Path) -> Result<(Document, u64), Fallacy> {
+    let conn = open(path)?;
+    let load = || -> rusqlite::Result<(Document, u64)> {
+        let document = Document {
+            next_id: read_next_id(&conn)?,
+            papers: read_papers(&conn, None)?,
+        };
+        Ok((document, read_generation(&conn)?))
+    };
+    load().map_err(|e| Fallacy::StateDatabaseFailed(path.to_owned(), e))
+}
+
+/// A number that grows whenever papers are written, so that sessions can
+/// tell whether another session changed the database without reading it.
+pub fn generation(path: &Path) -> Result<u64, Fallacy> {
+    let conn = open(path)?;
+    read_generation(&conn).map_err(|e| Fallacy::StateDatabaseFailed(path.to_owned(), e))
+}
+
+fn read_generation(conn: &Connection) -> rusqlite::Result<u64> {
+    // Databases written by older versions have no generation yet.
+    let generation = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'generation'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(generation.unwrap_or(0))
+}
+
+/// Raise the generation by one and return it.
+fn bump_generation(tx: &Transaction) -> rusqlite::Result<u64> {
+    let generation = read_generation(tx)? + 1;
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('generation', ?)",
+        [generation],
+    )?;
+    Ok(generation)
+}
+
+fn read_next_id(conn: &Connection) -> rusqlite::Result<u64> {
+    conn.query_row("SELECT value FROM meta WHERE key = 'next_id'", [], |row| {
+        row.get(0)
+    })
+}
+
+/// Raise the ID for the next paper to at least `next_id`.
+fn write_next_id(tx: &Transaction, next_id: u64) -> rusqlite::Result<()> {
+    tx.execute(
+        "UPDATE meta SET value = MAX(value, ?) WHERE key = 'next_id'",
+        [next_id],
+    )?;
+    Ok(())
+}
+
+/// Read all papers in the order of their IDs, or only the paper with the
+/// given ID.
+fn read_papers(conn: &Connection, id: Option<u64>) -> rusqlite::Result<Vec<Paper>> {
+    let (papers_where, others_where) = match id {
+        Some(_) => ("WHERE id = ?", "WHERE paper_id = ?"),
+        None => ("", ""),
+    };
+    let id_param = || rusqlite::params_from_iter(id.iter());
+
+    let mut papers = Vec::new();
+    let mut index = HashMap::new();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, title, nickname, venue, year, filepath, notepath, added, modified, opened, read
+         FROM papers {} ORDER BY id",
+        papers_where
+    ))?;
+    let mut rows = stmt.query(id_param())?;
+    while let Some(row) = rows.next()? {
+        let paper = Paper {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            nickname: row.get(2)?,
+            authors: Vec::new(),
+            venue: row.get(3)?,
+            year: row.get(4)?,
+            filepath: row.get::<_, Option<String>>(5)?.map(PathBuf::from),
+            labels: HashSet::new(),
+            notepath: row.get::<_, Option<String>>(6)?.map(PathBuf::from),
+            added: row.get(7)?,
+            modified: row.get(8)?,
+            opened: row.get(9)?,
+            read: row.get(10)?,
+            fields: BTreeMap::new(),
+        };
+        index.insert(paper.id, papers.len());
+        papers.push(paper);
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT paper_id, name FROM authors {} ORDER BY paper_id, position",
+        others_where
+    ))?;
+    let mut rows = stmt.query(id_param())?;
+    while let Some(row) = rows.next()? {
+        if let Some(&ind) = index.get(&row.get::<_, u64>(0)?) {
+            papers[ind].authors.push(row.get(1)?);
+        }
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT paper_id, label FROM labels {}",
+        others_where
+    ))?;
+    let mut rows = stmt.query(id_param())?;
+    while let Some(row) = rows.next()? {
+        if let Some(&ind) = index.get(&row.get::<_, u64>(0)?) {
+            papers[ind].labels.insert(row.get(1)?);
+        }
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT paper_id, key, value FROM fields {}",
+        others_where
+    ))?;
+    let mut rows = stmt.query(id_param())?;
+    while let Some(row) = rows.next()? {
+        if let Some(&ind) = index.get(&row.get::<_, u64>(0)?) {
+            papers[ind].fields.insert(row.get(1)?, row.get(2)?);
+        }
+    }
+    Ok(papers)
+}
+
+/// The paper with the given ID, if it is in the database.
+fn read_paper(conn: &Connection, id: u64) -> rusqlite::Result<Option<Paper>> {
+    Ok(read_papers(conn, Some(id))?.pop())
+}
+
+/// What `store` did.
+#[derive(Debug, Default)]
+pub struct Stored {
+    /// Descriptions of conflicts with changes made by other sessions.
+    pub conflicts: Vec<String>,
+    /// Whether another session wrote to the database since this session
+    /// last read it, in which case papers should be loaded again.
+    pub outdated: bool,
+    /// The generation of the database after the write.
+    pub generation: u64,
+}
+
+/// Write only the papers that changed since `base`, which is what the
+/// database looked like at `generation`, when this session last read it.
+/// Only the rows of those papers are read. Papers changed by other sessions
+/// in the meantime are merged like `merge::merge` does: a paper changed on
+/// both sides is merged field by field, and a paper removed on one side but
+/// modified on the other is kept. A new paper whose ID was taken by another
+/// session gets a new ID. The ID for the next paper is raised to at least
+/// `next_id`.
+pub fn store(
+    path: &Path,
+    base: &[Paper],
+    papers: &mut [Paper],
+    next_id: u64,
+    generation: u64,
+) -> Result<Stored, Fallacy> {
+    let mut conn = open(path)?;
+    let mut store = || -> rusqlite::Result<Stored> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let outdated = read_generation(&tx)? != generation;
+        let mut conflicts = Vec::new();
+
+        // Changed and removed papers.
+        let ours: HashMap<_, _> = papers.iter().map(|p| (p.id, p)).collect();
+        for base in base {
+            let our = ours.get(&base.id).copied();
+            if our == Some(base) {
+                continue;
+            }
+            let their = read_paper(&tx, base.id)?;
+            let (paper, conflict) = merge::merge_existing(base, our, their.as_ref());
+            conflicts.extend(conflict);
+            match paper {
+                Some(paper) if their.as_ref() != Some(&paper) => write_paper(&tx, &paper)?,
+                Some(_) => {}
+                None => delete_paper(&tx, base.id)?,
+            }
+        }
+
+        // Added papers.
+        let base: HashSet<_> = base.iter().map(|p| p.id).collect();
+        let max_id: Option<u64> =
+            tx.query_row("SELECT MAX(id) FROM papers", [], |row| row.get(0))?;
+        let mut next_id = max_id
+            .into_iter()
+            .chain(papers.iter().map(|p| p.id))
+            .map(|id| id + 1)
+            .chain([next_id, read_next_id(&tx)?])
+            .max()
+            .unwrap_or(1);
+        for paper in papers.iter_mut() {
+            if base.contains(&paper.id) {
+                continue;
+            }
+            let taken = tx
+                .query_row("SELECT 1 FROM papers WHERE id = ?", [paper.id], |_| Ok(()))
+                .optional()?
+                .is_some();
+            if taken {
+                conflicts.push(format!(
+                    "'{}': ID #{} was taken elsewhere. Its new ID is #{}.",
+                    paper.title, paper.id, next_id
+                ));
+                paper.id = next_id;
+                next_id += 1;
+            }
+            write_paper(&tx, paper)?;
+        }
+        write_next_id(&tx, next_id)?;
+        let generation = bump_generation(&tx)?;
+        tx.commit()?;
+        Ok(Stored {
+            conflicts,
+            outdated,
+            generation,
+        })
+    };
+    store().map_err(|e| Fallacy::StateDatabaseFailed(path.to_owned(), e))
+}
+
+/// Replace everything in the database with the given papers and the ID for
+/// the next paper.
+pub fn write_all(path: &Path, papers: &[Paper], next_id: u64) -> Result<(), Fallacy> {
+    let mut conn = open(path)?;
+    let mut write = || -> rusqlite::Result<()> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        tx.execute_batch(
+            "DELETE FROM papers; DELETE FROM authors; DELETE FROM labels; DELETE FROM fields;",
+        )?;
+        for paper in papers {
+            write_paper(&tx, paper)?;
+        }
+        tx.execute("UPDATE meta SET value = ? WHERE key = 'next_id'", [next_id])?;
+        bump_generation(&tx)?;
+        tx.commit()
+    };
+    write().map_err(|e| Fallacy::StateDatabaseFailed(path.to_owned(), e))
+}
+
+fn delete_paper(tx: &Transaction, id: u64) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM papers WHERE id = ?", [id])?;
+    tx.execute("DELETE FROM authors WHERE paper_id = ?", [id])?;
+    tx.execute("DELETE FROM labels WHERE paper_id = ?", [id])?;
+    tx.execute("DELETE FROM fields WHERE paper_id = ?", [id])?;
+    Ok(())
+}
+
+fn write_paper(tx: &Transaction, paper: &Paper) -> rusqlite::Result<()> {
+    delete_paper(tx, paper.id)?;
+    tx.execute(
+        "INSERT INTO papers (id, title, nickname, venue, year, filepath, notepath,
+                             added, modified, opened, read)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            paper.id,
+            paper.title,
+            paper.nickname,
+            paper.venue,
+            paper.year,
+            paper.filepath.as_ref().map(|p| p.to_string_lossy()),
+            paper.notepath.as_ref().map(|p| p.to_string_lossy()),
+            paper.added,
+            paper.modified,
+            paper.opened,
+            paper.read,
+        ],
+    )?;
+    for (position, name) in paper.authors.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO authors (paper_id, position, name) VALUES (?, ?, ?)",
+            params![paper.id, position, name],
+        )?;
+    }
+    for label in paper.labels.iter() {
+        tx.execute(
+            "INSERT INTO labels (paper_id, label) VALUES (?, ?)",
+            params![paper.id, label],
+        )?;
+    }
+    for (key, value) in paper.fields.iter() {
+        tx.execute(
+            "INSERT INTO fields (paper_id, key, value) VALUES (?, ?, ?)",
+            params![paper.id, key, value],
+        )?;
+    }
+    Ok(())
+}
+
+/// Run the filter as a query and return the IDs of matching papers, in the
+/// order of their IDs. Returns `None` if the filter cannot be written in SQL.
+pub fn query(path: &Path, filter: &PaperFilter) -> Result<Option<Vec<u64>>, Fallacy> {
+    let mut regexes = Vec::new();
+    let mut conditions = Vec::new();
+    for term in filter.terms.iter() {
+        match condition(term, &mut regexes) {
+            Some(condition) => conditions.push(condition),
+            None => return Ok(None),
+        }
+    }
+    let sql = match conditions.is_empty() {
+        true => "SELECT id FROM papers ORDER BY id".to_owned(),
+        false => format!(
+            "SELECT id FROM papers WHERE {} ORDER BY id",
+            conditions.join(" AND ")
+        ),
+    };
+
+    let conn = open(path)?;
+    let query = || -> rusqlite::Result<Vec<u64>> {
+        // Regexes are matched by reason, so that they behave the same as
+        // when filtering papers in memory.
+        let flags = || FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+        conn.create_scalar_function("reason_match", 2, flags(), move |ctx| {
+            let regex: usize = ctx.get(0)?;
+            let text: String = ctx.get(1)?;
+            Ok(regexes[regex].is_match(&text))
+        })?;
+        conn.create_scalar_function("reason_year", 1, flags(), |ctx| {
+            Ok(parse_year(&ctx.get::<String>(0)?))
+        })?;
+        conn.create_scalar_function("reason_time", 1, flags(), |ctx| {
+            let time = ctx.get::<Option<String>>(0)?;
+            Ok(time
+                .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                .map(|time| time.timestamp()))
+        })?;
+        let mut stmt = conn.prepare(&sql)?;
+        let ids = stmt.query_map([], |row| row.get(0))?;
+        ids.collect()
+    };
+    query()
+        .map(Some)
+        .map_err(|e| Fallacy::StateDatabaseFailed(path.to_owned(), e))
+}
+
+/// Translate a filter expression into an SQL condition on the papers table.
+/// Regexes are collected into `regexes` and referred to by index.
+fn condition(expr: &FilterExpr, regexes: &mut Vec<Regex>) -> Option<String> {
+    Some(match expr {
+        FilterExpr::Match(field, regex) => {
+            let ind = regexes.len();
+            regexes.push(regex.clone());
+            let exists = |table: &str, column: &str, extra: &str| {
+                format!(
+                    "EXISTS (SELECT 1 FROM {0} WHERE {0}.paper_id = papers.id{1} AND reason_match({2}, {0}.{3}))",
+                    table, extra, ind, column
+                )
+            };
+            match field {
+                Field::Title => format!("reason_match({}, title)", ind),
+                Field::Nickname => format!("reason_match({}, COALESCE(nickname, ''))", ind),
+                Field::Author => exists("authors", "name", ""),
+                Field::FirstAuthor => exists("authors", "name", " AND authors.position = 0"),
+                Field::Venue => format!("reason_match({}, venue)", ind),
+                Field::Year => format!("reason_match({}, year)", ind),
+                Field::Label => exists("labels", "label", ""),
+            }
+        }
+        // Years that are not numbers are NULL, so comparisons on them never
+        // match, even when negated, as in memory.
+        FilterExpr::Year(cmp) => {
+            let bounds = match *cmp {
+                YearCmp::Range(min, max) => {
+                    let mut bounds = Vec::new();
+                    bounds.extend(min.map(|min| format!("reason_year(year) >= {}", min)));
+                    bounds.extend(max.map(|max| format!("reason_year(year) <= {}", max)));
+                    bounds
+                }
+                YearCmp::Lt(bound) => vec![format!("reason_year(year) < {}", bound)],
+                YearCmp::Le(bound) => vec![format!("reason_year(year) <= {}", bound)],
+                YearCmp::Gt(bound) => vec![format!("reason_year(year) > {}", bound)],
+                YearCmp::Ge(bound) => vec![format!("reason_year(year) >= {}", bound)],
+            };
+            format!("({})", bounds.join(" AND "))
+        }
+        FilterExpr::Id(id) => format!("id = {}", id),
+        // Papers to which the event never happened never match.
+        FilterExpr::Time(event, cmp) => {
+            let (op, time) = match cmp {
+                TimeCmp::Since(time) => (">=", time),
+                TimeCmp::Before(time) => ("<", time),
+            };
+            format!(
+                "COALESCE(reason_time(\"{}\") {} {}, 0)",
+                event.name(),
+                op,
+                time.timestamp()
+            )
+        }
+        FilterExpr::Where(key, regex) => {
+            let matches = match regex {
+                Some(regex) => {
+                    regexes.push(regex.clone());
+                    format!(" AND reason_match({}, fields.value)", regexes.len() - 1)
+                }
+                None => String::new(),
+            };
+            format!(
+                "EXISTS (SELECT 1 FROM fields WHERE fields.paper_id = papers.id AND fields.key = '{}'{})",
+                key.replace('\'', "''"),
+                matches
+            )
+        }
+        FilterExpr::Not(expr) => format!("NOT {}", condition(expr, regexes)?),
+        FilterExpr::And(exprs) => join(exprs, " AND ", regexes)?,
+        FilterExpr::Or(exprs) => join(exprs, " OR ", regexes)?,
+    })
+}
+
+/// Join the conditions of expressions with an operator.
+fn join(exprs: &[FilterExpr], op: &str, regexes: &mut Vec<Regex>) -> Option<String> {
+    let conditions = exprs
+        .iter()
+        .map(|e| condition(e, regexes))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", conditions.join(op)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{paper, papers, titles};
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.sqlite3");
+        let mut papers = papers();
+        papers[0].nickname = Some("spark".to_owned());
+        papers[0].filepath = Some(PathBuf::from("spark.pdf"));
+        papers[0]
+            .fields
+            .insert("code".to_owned(), "github.com/apache/spark".to_owned());
+        write_all(&path, &papers, 4).unwrap();
+        let document = Document {
+            next_id: 4,
+            papers: papers.clone(),
+        };
+        assert_eq!(load(&path).unwrap(), (document, 1));
+
+        // Incremental writes.
+        let base = papers.clone();
+        papers.remove(1);
+        papers[0].labels.insert("favorite".to_owned());
+        papers.push(paper(4, "Ray").by(&["Philipp"]).in_year("2018"));
+        let stored = store(&path, &base, &mut papers, 4, 1).unwrap();
+        assert!(stored.conflicts.is_empty());
+        assert!(!stored.outdated);
+        assert_eq!(generation(&path).unwrap(), stored.generation);
+        let document = Document { next_id: 5, papers };
+        assert_eq!(load(&path).unwrap(), (document, 2));
+    }
+
+    #[test]
+    fn next_id_survives_removal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.sqlite3");
+        let base = papers();
+        write_all(&path, &base, 4).unwrap();
+
+        let mut papers = base.clone();
+        papers.pop();
+        assert!(store(&path, &base, &mut papers, 4, 1)
+            .unwrap()
+            .conflicts
+            .is_empty());
+        assert_eq!(load(&path).unwrap().0.next_id, 4);
+    }
+
+    #[test]
+    fn id_taken_elsewhere() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.sqlite3");
+        let base = papers();
+        write_all(&path, &base, 4).unwrap();
+
+        // Another session added #4.
+        let mut theirs = base.clone();
+        theirs.push(paper(4, "Theirs").in_year("2020"));
+        store(&path, &base, &mut theirs, 5, 1).unwrap();
+
+        let mut ours = base.clone();
+        ours.push(paper(4, "Ours").in_year("2021"));
+        let stored = store(&path, &base, &mut ours, 5, 1).unwrap();
+        assert_eq!(stored.conflicts.len(), 1);
+        assert!(stored.outdated);
+        assert_eq!(ours[3].id, 5);
+        let (document, _) = load(&path).unwrap();
+        assert_eq!(
+            titles(&document.papers),
+            vec!["Spark", "Ray", "Preprint", "Theirs", "Ours"]
+        );
+    }
+
+    #[test]
+    fn changed_elsewhere() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.sqlite3");
+        let base = papers();
+        write_all(&path, &base, 4).unwrap();
+
+        // Another session changed Spark and Ray.
+        let mut theirs = base.clone();
+        theirs[0].venue = "NSDI".to_owned();
+        theirs[1].title = "Ray 2".to_owned();
+        assert!(store(&path, &base, &mut theirs, 4, 1)
+            .unwrap()
+            .conflicts
+            .is_empty());
+
+        // We changed Spark too, and removed Ray.
+        let mut ours = base.clone();
+        ours[0].year = "2013".to_owned();
+        ours.remove(1);
+        assert_eq!(
+            store(&path, &base, &mut ours, 4, 1)
+                .unwrap()
+                .conflicts
+                .len(),
+            1
+        );
+
+        let (document, _) = load(&path).unwrap();
+        let stored = document.papers;
+        assert_eq!(titles(&stored), vec!["Spark", "Ray 2", "Preprint"]);
+        assert_eq!(
+            (stored[0].venue.as_str(), stored[0].year.as_str()),
+            ("NSDI", "2013")
+        );
+    }
+
+    #[test]
+    fn query_matches_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.sqlite3");
+        let papers = papers();
+        write_all(&path, &papers, 4).unwrap();
+
+        let filters = [
+            "",
+            "by Ion",
+            "by1 Ion",
+            "is done or is ml",
+            "not done",
+            "in 2010..2015",
+            "not in >=2015",
+            "not ( in >=2015 or is ml )",
+            "#2",
+            "( Spark or by Someone ) not in 2012",
+            "where dataset=Image",
+            "not where dataset",
+            "added since 2020-06-01",
+            "not added before 2020-06-01",
+        ];
+        for filter in filters.iter() {
+            let args: Vec<_> = filter.split_whitespace().map(|s| s.to_owned()).collect();
+            let filter = PaperFilter::from_args(&args, false).unwrap();
+            let expected: Vec<_> = papers
+                .iter()
+                .filter(|p| filter.matches(p))
+                .map(|p| p.id)
+                .collect();
+            assert_eq!(query(&path, &filter).unwrap(), Some(expected), "{}", filter);
+        }
+    }
+}
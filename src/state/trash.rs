@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::config::StorageConfig;
+use crate::error::Fallacy;
+use crate::paper::Paper;
+use crate::state::write_atomic;
+
+/// Papers removed with `rm`, kept with their files and notes until the
+/// trash is emptied.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Trash {
+    pub entries: Vec<TrashEntry>,
+}
+
+/// A removed paper.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    pub paper: Paper,
+    /// When the paper was removed, in RFC 3339.
+    pub removed: String,
+    pub files: Vec<TrashedFile>,
+}
+
+/// A file of a removed paper. `path` is where the file was before it was
+/// moved to `trashed` inside the trash directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashedFile {
+    pub path: PathBuf,
+    pub trashed: PathBuf,
+}
+
+impl Trash {
+    /// Load the list of removed papers from the trash directory. A missing or
+    /// broken list is not fatal; files in the trash directory stay there.
+    pub fn load(config: &StorageConfig) -> Self {
+        let path = config.trash_dir().join("trash.yaml");
+        if !path.exists() {
+            return Self::default();
+        }
+        match File::open(&path).map(serde_yaml::from_reader) {
+            Ok(Ok(trash)) => trash,
+            _ => {
+                eprintln!("Failed to load the list of removed papers from {:?}.", path);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn store(&self, config: &StorageConfig) -> Result<(), Fallacy> {
+        let path = config.trash_dir().join("trash.yaml");
+        let serialized = match serde_yaml::to_string(self) {
+            Ok(s) => s,
+            Err(e) => return Err(Fallacy::TrashStoreFailed(path, e)),
+        };
+        write_atomic(&path, &serialized)?;
+        Ok(())
+    }
+}
+
+impl TrashEntry {
+    pub fn new(paper: Paper) -> Self {
+        Self {
+            paper,
+            removed: Local::now().to_rfc3339(),
+            files: Vec::new(),
+        }
+    }
+
+    /// When the paper was removed.
+    pub fn removed(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.removed)
+            .ok()
+            .map(|time| time.with_timezone(&Local))
+    }
+
+    /// Whether the paper was removed more than `days` days ago.
+    /// Entries with broken timestamps are considered old.
+    pub fn is_older_than(&self, days: i64) -> bool {
+        self.removed()
+            .is_none_or(|removed| removed < Local::now() - Duration::days(days))
+    }
+}
+
+/// A path inside the trash directory to move a file to.
+pub fn trashed_path(path: &Path, config: &StorageConfig) -> PathBuf {
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    crate::utils::make_unique_path(&config.trash_dir(), &name, &ext)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn older_than() {
+        let mut entry = TrashEntry::new(Paper::default());
+        assert!(!entry.is_older_than(1));
+        entry.removed = (Local::now() - Duration::days(3)).to_rfc3339();
+        assert!(entry.is_older_than(2));
+        assert!(!entry.is_older_than(4));
+        entry.removed = "yesterday".to_owned();
+        assert!(entry.is_older_than(4));
+    }
+
+    #[test]
+    fn store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StorageConfig {
+            trash_dir: Some(dir.path().join("trash")),
+            ..Default::default()
+        };
+        let trash = Trash {
+            entries: vec![TrashEntry::new(Paper::default())],
+        };
+        trash.store(&config).unwrap();
+        assert_eq!(Trash::load(&config).entries, trash.entries);
+        // Only the list itself is in the trash directory.
+        assert_eq!(std::fs::read_dir(config.trash_dir()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::StorageConfig;
+use crate::error::Fallacy;
+use crate::paper::Paper;
+use crate::state::trash::TrashEntry;
+use crate::state::write_atomic;
+
+/// Changes made by past commands, which can be undone and redone.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct UndoHistory {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    /// Files moved by the command that is currently running.
+    #[serde(skip)]
+    pending: Vec<MovedFile>,
+}
+
+/// The changes made by one command.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Change {
+    /// The command line that made the change.
+    pub command: String,
+    papers: Vec<Diff<Paper>>,
+    trash: Vec<Diff<TrashEntry>>,
+    files: Vec<MovedFile>,
+}
+
+/// A record before and after a command. `None` means the record did not exist.
+#[derive(Serialize, Deserialize, Debug)]
+struct Diff<T> {
+    before: Option<T>,
+    after: Option<T>,
+}
+
+/// A file moved by a command, e.g. to the trash.
+#[derive(Serialize, Deserialize, Debug)]
+struct MovedFile {
+    from: PathBuf,
+    to: PathBuf,
+}
+
+/// Records that commands change, identified by paper IDs.
+trait Record: Clone + PartialEq {
+    fn id(&self) -> u64;
+    fn title(&self) -> &str;
+
+    /// The record without the times it was accessed, which are not changes
+    /// that can be undone.
+    fn without_access(&self) -> Self {
+        self.clone()
+    }
+
+    /// Keep the access times of the current record.
+    fn keep_access(&mut self, _current: &Self) {}
+}
+
+impl Record for Paper {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn without_access(&self) -> Self {
+        Paper::without_access(self)
+    }
+
+    fn keep_access(&mut self, current: &Self) {
+        self.opened = current.opened.clone();
+        self.read = current.read.clone();
+    }
+}
+
+impl Record for TrashEntry {
+    fn id(&self) -> u64 {
+        self.paper.id
+    }
+
+    fn title(&self) -> &str {
+        &self.paper.title
+    }
+}
+
+impl UndoHistory {
+    /// Load the history from the file next to the command history file.
+    /// A missing or broken history file is not fatal; it just means there
+    /// is nothing to undo.
+    pub fn load(config: &StorageConfig) -> Self {
+        let path = config.undo_history();
+        if !path.exists() {
+            return Self::default();
+        }
+        match File::open(&path).map(serde_yaml::from_reader) {
+            Ok(Ok(history)) => history,
+            _ => {
+                eprintln!("Failed to load undo history from {:?}.", path);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn store(&self, config: &StorageConfig) -> Result<(), Fallacy> {
+        let path = config.undo_history();
+        let serialized = match serde_yaml::to_string(self) {
+            Ok(s) => s,
+            Err(e) => return Err(Fallacy::UndoStoreFailed(path, e)),
+        };
+        write_atomic(&path, &serialized)?;
+        Ok(())
+    }
+
+    /// Move a file and remember it, so that undoing the running command
+    /// moves the file back.
+    pub fn move_file(&mut self, from: &Path, to: &Path) -> Result<(), Fallacy> {
+        crate::utils::move_file(from, to)?;
+        self.pending.push(MovedFile {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        });
+        Ok(())
+    }
+
+    /// Record the changes made by a command, given the papers and the trash
+    /// before and after the command. Commands that changed nothing are not
+    /// recorded.
+    pub fn record(
+        &mut self,
+        command: &str,
+        before: (&[Paper], &[TrashEntry]),
+        after: (&[Paper], &[TrashEntry]),
+        config: &StorageConfig,
+    ) {
+        let papers = diff(before.0, after.0);
+        let trash = diff(before.1, after.1);
+        let files = std::mem::take(&mut self.pending);
+        if papers.is_empty() && trash.is_empty() && files.is_empty() {
+            return;
+        }
+
+        self.undo.push(Change {
+            command: command.to_owned(),
+            papers,
+            trash,
+            files,
+        });
+        self.redo.clear();
+
+        // Forget the oldest changes.
+        let max_undo = config.max_undo();
+        if self.undo.len() > max_undo {
+            self.undo.drain(..self.undo.len() - max_undo);
+        }
+    }
+
+    /// Revert the last change. Returns the command that made the change.
+    pub fn undo(
+        &mut self,
+        papers: &mut Vec<Paper>,
+        trash: &mut Vec<TrashEntry>,
+    ) -> Result<String, Fallacy> {
+        let change = match self.undo.pop() {
+            Some(change) => change,
+            None => return Err(Fallacy::UndoNothing),
+        };
+        if let Err(e) = change.revert(papers, trash) {
+            self.undo.push(change);
+            return Err(e);
+        }
+        let command = change.command.clone();
+        self.redo.push(change);
+        Ok(command)
+    }
+
+    /// Apply the last undone change again. Returns the command that made the change.
+    pub fn redo(
+        &mut self,
+        papers: &mut Vec<Paper>,
+        trash: &mut Vec<TrashEntry>,
+    ) -> Result<String, Fallacy> {
+        let change = match self.redo.pop() {
+            Some(change) => change,
+            None => return Err(Fallacy::RedoNothing),
+        };
+        if let Err(e) = change.apply(papers, trash) {
+            self.redo.push(change);
+            return Err(e);
+        }
+        let command = change.command.clone();
+        self.undo.push(change);
+        Ok(command)
+    }
+}
+
+impl Change {
+    /// Turn records after the change into records before it, and move
+    /// files back.
+    fn revert(&self, papers: &mut Vec<Paper>, trash: &mut Vec<TrashEntry>) -> Result<(), Fallacy> {
+        let paper_changes = self.papers.iter().map(|d| (&d.after, &d.before));
+        let trash_changes = self.trash.iter().map(|d| (&d.after, &d.before));
+        check(papers, paper_changes.clone())?;
+        check(trash, trash_changes.clone())?;
+        for file in self.files.iter().rev() {
+            crate::utils::move_file(&file.to, &file.from)?;
+        }
+        replace(papers, paper_changes);
+        replace(trash, trash_changes);
+        Ok(())
+    }
+
+    /// Turn records before the change into records after it, and move
+    /// files again.
+    fn apply(&self, papers: &mut Vec<Paper>, trash: &mut Vec<TrashEntry>) -> Result<(), Fallacy> {
+        let paper_changes = self.papers.iter().map(|d| (&d.before, &d.after));
+        let trash_changes = self.trash.iter().map(|d| (&d.before, &d.after));
+        check(papers, paper_changes.clone())?;
+        check(trash, trash_changes.clone())?;
+        for file in self.files.iter() {
+            crate::utils::move_file(&file.from, &file.to)?;
+        }
+        replace(papers, paper_changes);
+        replace(trash, trash_changes);
+        Ok(())
+    }
+}
+
+/// Records that differ between `before` and `after`.
+fn diff<T: Record>(before: &[T], after: &[T]) -> Vec<Diff<T>> {
+    let before_ids: HashMap<_, _> = before.iter().map(|r| (r.id(), r)).collect();
+    let after_ids: HashMap<_, _> = after.iter().map(|r| (r.id(), r)).collect();
+    let mut diffs = Vec::new();
+    for record in before {
+        match after_ids.get(&record.id()).copied() {
+            Some(changed) if changed.without_access() == record.without_access() => {}
+            changed => diffs.push(Diff {
+                before: Some(record.clone()),
+                after: changed.cloned(),
+            }),
+        }
+    }
+    for record in after {
+        if !before_ids.contains_key(&record.id()) {
+            diffs.push(Diff {
+                before: None,
+                after: Some(record.clone()),
+            });
+        }
+    }
+    diffs
+}
+
+/// Check that all records are still `from`. They may not be if, for instance,
+/// another session changed them after the command.
+fn check<'c, T: Record + 'c>(
+    records: &[T],
+    changes: impl Iterator<Item = (&'c Option<T>, &'c Option<T>)>,
+) -> Result<(), Fallacy> {
+    let current: HashMap<_, _> = records.iter().map(|r| (r.id(), r)).collect();
+    for (from, to) in changes {
+        let record = match from.as_ref().or(to.as_ref()) {
+            Some(record) => record,
+            None => continue,
+        };
+        let current = current.get(&record.id()).copied();
+        if current.map(T::without_access) != from.as_ref().map(T::without_access) {
+            return Err(Fallacy::UndoConflict(record.title().to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Replace records that are `from` with `to`, keeping their access times.
+fn replace<'c, T: Record + 'c>(
+    records: &mut Vec<T>,
+    changes: impl Iterator<Item = (&'c Option<T>, &'c Option<T>)>,
+) {
+    for (from, to) in changes {
+        match (from, to) {
+            (Some(from), Some(to)) => {
+                if let Some(record) = records.iter_mut().find(|r| r.id() == from.id()) {
+                    let mut to = to.clone();
+                    to.keep_access(record);
+                    *record = to;
+                }
+            }
+            (Some(from), None) => records.retain(|r| r.id() != from.id()),
+            // IDs increase in the order papers are added, so this puts the
+            // record back where it was.
+            (None, Some(to)) => {
+                let ind = records
+                    .iter()
+                    .position(|r| r.id() > to.id())
+                    .unwrap_or(records.len());
+                records.insert(ind, to.clone());
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{paper, titles};
+
+    #[test]
+    fn undo_redo() {
+        let config = StorageConfig::default();
+        let mut history = UndoHistory::default();
+        let before = vec![paper(1, "A"), paper(2, "B"), paper(3, "C")];
+        let mut papers = vec![paper(1, "A2"), paper(3, "C"), paper(4, "D")];
+        let mut trash = vec![TrashEntry::new(paper(2, "B"))];
+        history.record("cmd", (&before, &[]), (&papers, &trash), &config);
+
+        assert_eq!(history.undo(&mut papers, &mut trash).unwrap(), "cmd");
+        assert_eq!(titles(&papers), vec!["A", "B", "C"]);
+        assert!(trash.is_empty());
+        assert!(history.undo(&mut papers, &mut trash).is_err());
+
+        assert_eq!(history.redo(&mut papers, &mut trash).unwrap(), "cmd");
+        assert_eq!(titles(&papers), vec!["A2", "C", "D"]);
+        assert_eq!(trash.len(), 1);
+        assert!(history.redo(&mut papers, &mut trash).is_err());
+    }
+
+    #[test]
+    fn conflict() {
+        let config = StorageConfig::default();
+        let mut history = UndoHistory::default();
+        let before = vec![paper(1, "A")];
+        history.record("cmd", (&before, &[]), (&[paper(1, "A2")], &[]), &config);
+
+        // Changed by someone else after the command.
+        let mut papers = vec![paper(1, "A3")];
+        assert!(history.undo(&mut papers, &mut Vec::new()).is_err());
+        assert_eq!(titles(&papers), vec!["A3"]);
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let config = StorageConfig::default();
+        let mut history = UndoHistory::default();
+        let papers = vec![paper(1, "A")];
+        history.record("ls", (&papers, &[]), (&papers, &[]), &config);
+        assert!(history.undo.is_empty());
+    }
+
+    #[test]
+    fn access_times() {
+        let config = StorageConfig::default();
+        let mut history = UndoHistory::default();
+        let before = vec![paper(1, "A")];
+        let mut opened = before.clone();
+        opened[0].opened = Some("2020-01-01T00:00:00+00:00".to_owned());
+        history.record("open", (&before, &[]), (&opened, &[]), &config);
+        assert!(history.undo.is_empty());
+
+        // Undoing a change keeps the time the paper was opened.
+        let mut papers = vec![paper(1, "A2")];
+        history.record("set", (&opened, &[]), (&papers, &[]), &config);
+        papers[0].opened = Some("2020-01-02T00:00:00+00:00".to_owned());
+        history.undo(&mut papers, &mut Vec::new()).unwrap();
+        assert_eq!(titles(&papers), vec!["A"]);
+        assert_eq!(
+            papers[0].opened.as_deref(),
+            Some("2020-01-02T00:00:00+00:00")
+        );
+    }
+}
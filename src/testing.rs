@@ -0,0 +1,61 @@
+//! Paper fixtures shared by unit tests.
+
+use crate::paper::Paper;
+
+/// A paper with only an ID and a title. Other fields are set by chaining
+/// the methods below, e.g. `paper(1, "Spark").by(&["Matei"]).in_year("2012")`.
+pub fn paper(id: u64, title: &str) -> Paper {
+    Paper {
+        id,
+        title: title.to_owned(),
+        ..Default::default()
+    }
+}
+
+/// A small paperbase with authors, labels, a custom field, and timestamps.
+pub fn papers() -> Vec<Paper> {
+    let mut papers = vec![
+        paper(1, "Spark")
+            .by(&["Matei", "Ion"])
+            .in_year("2012")
+            .labeled(&["done"]),
+        paper(2, "Ray").by(&["Philipp", "Ion"]).in_year("2018"),
+        paper(3, "Preprint")
+            .by(&["Someone"])
+            .in_year("n.d.")
+            .labeled(&["active", "ml"]),
+    ];
+    papers[2]
+        .fields
+        .insert("dataset".to_owned(), "ImageNet".to_owned());
+    papers[0].added = Some("2020-01-01T12:00:00+09:00".to_owned());
+    papers[1].added = Some("2021-01-01T12:00:00+00:00".to_owned());
+    papers
+}
+
+/// Titles of the papers, in order.
+pub fn titles(papers: &[Paper]) -> Vec<&str> {
+    papers.iter().map(|p| p.title.as_str()).collect()
+}
+
+impl Paper {
+    pub fn by(mut self, authors: &[&str]) -> Self {
+        self.authors = authors.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn at(mut self, venue: &str) -> Self {
+        self.venue = venue.to_owned();
+        self
+    }
+
+    pub fn in_year(mut self, year: &str) -> Self {
+        self.year = year.to_owned();
+        self
+    }
+
+    pub fn labeled(mut self, labels: &[&str]) -> Self {
+        self.labels = labels.iter().map(|s| s.to_string()).collect();
+        self
+    }
+}
@@ -49,6 +49,19 @@ pub fn expand_tilde_str(path: &str) -> Result<String, Fallacy> {
     }
 }
 
+/// Move a file. Falls back to copying and removing the file if the
+/// destination is on another file system.
+pub fn move_file(from: &Path, to: &Path) -> Result<(), Fallacy> {
+    if let Some(dir) = to.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
 /// Ask the user to input something.
 /// Automatically appends ": " to the prompt string.
 pub fn ask_for(prompt: &str, default: Option<String>) -> Result<String, Fallacy> {
@@ -62,13 +75,23 @@ pub fn ask_for(prompt: &str, default: Option<String>) -> Result<String, Fallacy>
     // Get input.
     let mut buffer = String::new();
     stdin().read_line(&mut buffer)?;
-    if buffer.trim().is_empty() && default.is_some() {
-        buffer = default.unwrap();
+    if buffer.trim().is_empty() {
+        if let Some(default) = default {
+            buffer = default;
+        }
     }
 
     Ok(buffer.trim().to_string())
 }
 
+/// An HTTP client that identifies itself as this version of reason.
+pub fn http_client() -> Result<reqwest::blocking::Client, Fallacy> {
+    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    Ok(reqwest::blocking::ClientBuilder::new()
+        .user_agent(user_agent)
+        .build()?)
+}
+
 /// Ask confirmation to the user.
 pub fn confirm(prompt: String, default: bool) -> Result<(), Fallacy> {
     // Ask.